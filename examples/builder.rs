@@ -0,0 +1,21 @@
+use cool_rust_input::{CoolInput, DefaultInputHandler, InputConstraints, Keymap, ScreenMode};
+use crossterm::event::{KeyCode, KeyModifiers};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut my_input = CoolInput::builder(DefaultInputHandler)
+        .text("Dear ,")
+        .cursor_at(5, 0)
+        .screen_mode(ScreenMode::Alternate)
+        // quit on CTRL + Q as well as Escape
+        .keymap(Keymap::new().bind(
+            KeyCode::Char('q'),
+            KeyModifiers::CONTROL,
+            KeyCode::Esc,
+            KeyModifiers::NONE,
+        ))
+        .constraints(InputConstraints::default().max_lines(20))
+        .build()?;
+    my_input.listen()?;
+    println!("{}", my_input.text_data.text);
+    Ok(())
+}
@@ -1,35 +1,99 @@
 // https://github.com/ingobeans/banano
 
 use cool_rust_input::{
-    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputTransform,
-    KeyPressResult,
+    diff_lines, set_terminal_line, Confirm, CoolInput, CustomInputHandler, DiffLine, DiskChange,
+    Document, DocumentError, HandlerContext, InputTransform, KeyPressResult, LineNumbers, Sign,
+    SwapFile, TextInputData,
 };
-use crossterm::cursor;
-use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::event::{Event, KeyCode, KeyModifiers};
 use crossterm::style::{ResetColor, SetBackgroundColor};
 use crossterm::{
     queue,
     style::{Color, SetForegroundColor},
 };
+use std::collections::HashMap;
 use std::env;
-use std::fs;
-use std::io::stdout;
+use std::io::{self, stdout, ErrorKind};
+use std::time::Duration;
 
-fn save_file(filename: &str, text: &str) {
-    fs::write(filename, text).expect("Unable to write new contents.");
-}
 pub struct FileEditorInput {
     pub filename: String,
-    original_text: String,
+    document: Document,
+    /// Keeps unsaved edits, in case the editor is killed
+    swap_file: SwapFile,
     is_new: bool,
+    /// Error from the last save, shown instead of the file status
+    save_error: Option<String>,
+    /// Set when the file was changed by something else, until the user reloads or keeps their text
+    disk_change: Option<DiskChange>,
+    /// Signs for the lines that differ from the file on disk, while the diff is shown
+    diff_signs: Option<HashMap<usize, Sign>>,
 }
 impl FileEditorInput {
-    fn open_filename(filename: String, original_text: String, is_new: bool) -> Self {
-        FileEditorInput {
+    fn open_filename(filename: String, text_data: &mut TextInputData) -> io::Result<Self> {
+        let (document, is_new) = match Document::open(&filename, text_data) {
+            Ok(document) => (document, false),
+            Err(DocumentError::Io(error)) if error.kind() == ErrorKind::NotFound => {
+                (Document::new(&filename), true)
+            }
+            Err(error) => return Err(io::Error::other(error)),
+        };
+        Ok(FileEditorInput {
+            swap_file: SwapFile::for_path(&filename),
             filename,
-            original_text,
+            document,
             is_new,
+            save_error: None,
+            disk_change: None,
+            diff_signs: None,
+        })
+    }
+    fn save(&mut self, text_data: &TextInputData) {
+        match self.document.save(text_data) {
+            Ok(()) => {
+                self.is_new = false;
+                self.save_error = None;
+                let _ = self.swap_file.remove();
+            }
+            Err(error) => self.save_error = Some(format!("SAVE FAILED: {error}")),
+        }
+    }
+    fn reload(&mut self, text_data: &mut TextInputData) {
+        match self.document.reload(text_data) {
+            Ok(()) => {
+                self.disk_change = None;
+                self.diff_signs = None;
+                let _ = self.swap_file.remove();
+            }
+            Err(error) => self.save_error = Some(format!("RELOAD FAILED: {error}")),
+        }
+    }
+    fn keep_text(&mut self) {
+        let _ = self.document.keep_text();
+        self.disk_change = None;
+        self.diff_signs = None;
+    }
+    /// Show or hide which lines differ from the file on disk
+    fn toggle_diff(&mut self, text_data: &TextInputData) {
+        if self.diff_signs.take().is_some() {
+            return;
+        }
+        let disk_text = self.document.read_disk_text().unwrap_or_default();
+        let mut signs = HashMap::new();
+        let mut line = 0;
+        for diff_line in diff_lines(&disk_text, &text_data.text) {
+            match diff_line {
+                DiffLine::Same(_) => line += 1,
+                DiffLine::Added(_) => {
+                    signs.insert(line, Sign::changed());
+                    line += 1;
+                }
+                DiffLine::Removed(_) => {
+                    signs.entry(line).or_insert(Sign::error());
+                }
+            }
         }
+        self.diff_signs = Some(signs);
     }
 }
 impl CustomInputHandler for FileEditorInput {
@@ -44,9 +108,26 @@ impl CustomInputHandler for FileEditorInput {
                         }
                         // save on CTRL + S
                         if c == 's' {
-                            save_file(&self.filename, &ctx.text_data.text);
-                            self.is_new = false;
-                            self.original_text = ctx.text_data.text.to_owned();
+                            self.save(ctx.text_data);
+                            return KeyPressResult::Handled;
+                        }
+                        // when the file changed on disk, reload it on CTRL + R, keep the user's text on CTRL + K
+                        if self.disk_change.is_some() {
+                            match c {
+                                'r' => {
+                                    self.reload(ctx.text_data);
+                                    return KeyPressResult::Handled;
+                                }
+                                'k' => {
+                                    self.keep_text();
+                                    return KeyPressResult::Handled;
+                                }
+                                _ => {}
+                            }
+                        }
+                        // show what differs from the file on disk on CTRL + E
+                        if c == 'e' {
+                            self.toggle_diff(ctx.text_data);
                             return KeyPressResult::Handled;
                         }
                     }
@@ -65,12 +146,25 @@ impl CustomInputHandler for FileEditorInput {
         let center_text = format!("FILE: '{}'", self.filename);
         let mut right_text = "NOT MODIFIED";
 
-        if self.original_text != ctx.text_data.text {
+        if self.document.is_dirty(ctx.text_data) {
             right_text = "MODIFIED";
         }
         if self.is_new {
             right_text = "NEW FILE"
         }
+        if ctx.text_data.mixed_line_endings {
+            // Saving writes every line with the most used line ending
+            right_text = "MIXED LINE ENDINGS";
+        }
+        match self.disk_change {
+            Some(DiskChange::Modified) => right_text = "FILE CHANGED ON DISK",
+            Some(DiskChange::Deleted) => right_text = "FILE DELETED ON DISK",
+            _ => {}
+        }
+        let save_error = self.save_error.clone();
+        if let Some(save_error) = &save_error {
+            right_text = save_error;
+        }
 
         let bottom_text_position = (ctx.terminal_size.1 - 1) as usize;
         let width = self.get_input_transform(ctx).size.0;
@@ -82,10 +176,19 @@ impl CustomInputHandler for FileEditorInput {
             0,
             false,
         );
-        let _ = set_terminal_line(right_text, width as usize - right_text.len(), 0, false);
+        let _ = set_terminal_line(
+            right_text,
+            (width as usize).saturating_sub(right_text.len()),
+            0,
+            false,
+        );
 
-        let keybinds = ["^S".to_string(), "^C".to_string()];
-        let descriptions = ["Save File".to_string(), "Exit".to_string()];
+        let mut keybinds = vec!["^S", "^C", "^E"];
+        let mut descriptions = vec!["Save File", "Exit", "Diff"];
+        if self.disk_change.is_some() {
+            keybinds.extend(["^R", "^K"]);
+            descriptions.extend(["Reload", "Keep Mine"]);
+        }
 
         let mut offset = 0;
         for (keybind, description) in keybinds.iter().zip(descriptions) {
@@ -97,66 +200,30 @@ impl CustomInputHandler for FileEditorInput {
             let _ = set_terminal_line(keybind, offset, bottom_text_position, false);
             offset += keybind.chars().count() + 1;
             let _ = queue!(stdout(), ResetColor);
-            let _ = set_terminal_line(&description, offset, bottom_text_position, false);
+            let _ = set_terminal_line(description, offset, bottom_text_position, false);
             offset += description.chars().count() + 1;
         }
     }
-    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
-        let size = (ctx.terminal_size.0, ctx.terminal_size.1 - 3);
-        let offset = (0, 2);
-        InputTransform { size, offset }
-    }
-}
-
-pub fn path_exists(path: &str) -> bool {
-    fs::metadata(path).is_ok()
-}
-
-/// A simple Y/N prompt input handler. Automatically stops on first keypress, no enter required.
-pub struct ConfirmationInputHandler {
-    pub prompt: String,
-    pub value: bool,
-}
-impl ConfirmationInputHandler {
-    pub fn prompt(prompt: &str) -> Result<bool, std::io::Error> {
-        let handler = ConfirmationInputHandler {
-            prompt: prompt.to_string(),
-            value: false,
-        };
-        let mut input = CoolInput::new(handler, 0);
-        input.listen()?;
-        Ok(input.custom_input.value)
-    }
-}
-impl CustomInputHandler for ConfirmationInputHandler {
-    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
-        let prompt_offset = self.prompt.chars().count() as u16;
-        InputTransform {
-            size: (ctx.terminal_size.0 - prompt_offset, ctx.terminal_size.1),
-            offset: (prompt_offset, 0),
-        }
-    }
-    fn after_update_cursor(&mut self, _: HandlerContext) {
-        let _ = queue!(stdout(), cursor::Hide);
-    }
-    fn after_draw_text(&mut self, _: HandlerContext) {
-        let _ = set_terminal_line(&self.prompt, 0, 0, false);
+    fn get_sign(&mut self, line: usize, _: HandlerContext) -> Option<Sign> {
+        self.diff_signs.as_ref()?.get(&line).copied()
     }
-    fn handle_key_press(&mut self, key: &Event, _: HandlerContext) -> KeyPressResult {
-        if let Event::Key(key_event) = key {
-            if key_event.kind == KeyEventKind::Press {
-                // Make CTRL + C stop
-                if let KeyCode::Char(c) = key_event.code {
-                    if c == 'c' && key_event.modifiers.contains(KeyModifiers::CONTROL) {
-                        return KeyPressResult::Stop;
-                    } else if c == 'y' || c == 'n' {
-                        self.value = c == 'y';
-                        return KeyPressResult::Stop;
-                    }
+    fn on_tick(&mut self, ctx: HandlerContext) -> bool {
+        let _ = self.swap_file.autosave(&self.document, ctx.text_data);
+        // Look for changes made by other programs between keypresses
+        if self.disk_change.is_none() {
+            if let Ok(change) = self.document.check_disk() {
+                if change != DiskChange::Unchanged {
+                    self.disk_change = Some(change);
+                    return true;
                 }
             }
         }
-        KeyPressResult::Handled
+        false
+    }
+    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
+        let size = (ctx.terminal_size.0, ctx.terminal_size.1 - 3);
+        let offset = (0, 2);
+        InputTransform { size, offset }
     }
 }
 
@@ -167,23 +234,33 @@ fn main() -> Result<(), std::io::Error> {
         return Ok(());
     }
     let filename = &args[1];
-    let mut text = String::new();
-    let mut is_new = true;
-    if path_exists(filename) {
-        text = fs::read_to_string(filename).expect("Unable to read file contents.");
-        is_new = false;
+    let mut text_data = TextInputData::new(4);
+    let handler = FileEditorInput::open_filename(filename.to_string(), &mut text_data)?;
+    // A swap file left behind means the editor was killed with unsaved edits
+    if handler.swap_file.exists() {
+        let recover = Confirm::new("Recover unsaved changes?", true).ask()?;
+        if recover == Some(true) {
+            handler.swap_file.recover(&mut text_data)?;
+        }
     }
-    let mut cool_input = CoolInput::new(
-        FileEditorInput::open_filename(filename.to_string(), text.to_owned(), is_new),
-        0,
-    );
-    cool_input.text_data.text = text;
+    let mut cool_input = CoolInput::new(handler, 4);
+    cool_input.tick_rate = Some(Duration::from_millis(500));
+    // Makefiles need real tabs, and files already indented with tabs should stay that way
+    text_data.hard_tabs = filename.ends_with("Makefile") || text_data.text.contains('\t');
+    text_data.auto_indent = true;
+    cool_input.text_data = text_data;
+    cool_input.gutter.line_numbers = LineNumbers::Absolute;
     cool_input.listen()?;
-    if cool_input.custom_input.original_text != cool_input.text_data.text {
-        let save = ConfirmationInputHandler::prompt("Save file? [y/n]").unwrap();
-        if save {
-            save_file(filename, &cool_input.text_data.text);
+    let editor = &mut cool_input.custom_input;
+    if editor.document.is_dirty(&cool_input.text_data) {
+        let save = Confirm::new("Save file?", false).ask()?;
+        if save == Some(true) {
+            editor
+                .document
+                .save(&cool_input.text_data)
+                .map_err(io::Error::other)?;
         }
     }
+    editor.swap_file.remove()?;
     Ok(())
 }
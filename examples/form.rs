@@ -0,0 +1,20 @@
+use cool_rust_input::{CoolInput, Form, FormOutcome, InputConstraints, LabeledInput};
+
+fn main() -> Result<(), std::io::Error> {
+    let mut name = CoolInput::new(LabeledInput::new("Name:", (0, 0), (30, 1)), 0);
+    name.text_data.constraints = InputConstraints::single_line();
+    let mut email = CoolInput::new(LabeledInput::new("Email:", (0, 1), (30, 1)), 0);
+    email.text_data.constraints = InputConstraints::single_line();
+    let description = CoolInput::new(LabeledInput::new("Description:", (0, 3), (40, 5)), 4);
+
+    // Tab moves between fields, CTRL + S submits the form
+    let mut form = Form::new()
+        .field("name", name)
+        .field("email", email)
+        .field("description", description);
+    match form.listen()? {
+        FormOutcome::Submitted(values) => println!("{values:#?}"),
+        FormOutcome::Cancelled => println!("cancelled"),
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use cool_rust_input::{
-    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputTransform,
-    KeyPressResult,
+    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputConstraints,
+    InputTransform, KeyPressResult,
 };
 use crossterm::event::{Event, KeyCode};
 use crossterm::{
@@ -18,12 +18,6 @@ impl CustomInputHandler for CoolCustomInput {
                 if let KeyCode::Esc = key_event.code {
                     return KeyPressResult::Stop;
                 }
-                // Disallow the user pressing the character 'S'
-                if let KeyCode::Char(c) = key_event.code {
-                    if c == 's' || c == 'S' {
-                        return KeyPressResult::Handled;
-                    }
-                }
             }
         }
         KeyPressResult::Continue
@@ -54,6 +48,9 @@ impl CustomInputHandler for CoolCustomInput {
 
 fn main() -> Result<(), std::io::Error> {
     let mut cool_input = CoolInput::new(CoolCustomInput, 0);
+    // Disallow the character 'S', whether it is typed or pasted
+    cool_input.text_data.constraints =
+        InputConstraints::default().char_filter(|c| c != 's' && c != 'S');
     cool_input.listen()?;
     Ok(())
 }
@@ -0,0 +1,26 @@
+use cool_rust_input::{CandidateSource, FuzzyFinder};
+use std::fs;
+use std::path::PathBuf;
+
+// Walk a directory, yielding the paths of all files in it
+fn walk(root: PathBuf) -> impl Iterator<Item = String> {
+    let mut stack = vec![root];
+    std::iter::from_fn(move || loop {
+        let path = stack.pop()?;
+        if path.is_dir() {
+            if let Ok(entries) = fs::read_dir(&path) {
+                stack.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        } else {
+            return Some(path.display().to_string());
+        }
+    })
+}
+
+fn main() -> Result<(), std::io::Error> {
+    // Files are streamed in while you type
+    let source = CandidateSource::Iterator(Box::new(walk(PathBuf::from("."))));
+    let picked = FuzzyFinder::with_source("Find file:", source).ask()?;
+    println!("picked: {picked:?}");
+    Ok(())
+}
@@ -0,0 +1,11 @@
+use cool_rust_input::{CoolInput, MarkdownInputHandler};
+
+fn main() -> Result<(), std::io::Error> {
+    // Press CTRL + P to toggle the preview
+    let mut my_input = CoolInput::new(MarkdownInputHandler::new(), 4);
+    my_input.text_data.text = String::from(
+        "# Release notes\n\n- **Added** markdown highlighting\n- Press `CTRL + P` for a *preview*\n\nSee [the repo](https://github.com/ingobeans/cool-rust-input)",
+    );
+    my_input.listen()?;
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use cool_rust_input::{
+    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputOutcome, InputTransform,
+    KeyPressResult, Mask,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+
+struct PasswordHandler;
+impl CustomInputHandler for PasswordHandler {
+    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
+        let size = (ctx.terminal_size.0 - 10, 1);
+        let offset = (10, 0);
+        InputTransform { size, offset }
+    }
+    fn after_draw_text(&mut self, _: HandlerContext) {
+        let _ = set_terminal_line("password:", 0, 0, false);
+    }
+    fn handle_key_press(&mut self, key: &Event, _: HandlerContext) -> KeyPressResult {
+        if let Event::Key(key_event) = key {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Enter => return KeyPressResult::Submit,
+                    KeyCode::Esc => return KeyPressResult::Stop,
+                    _ => {}
+                }
+            }
+        }
+        KeyPressResult::Continue
+    }
+}
+
+fn main() -> Result<(), std::io::Error> {
+    let mut my_input = CoolInput::new(PasswordHandler, 0);
+    my_input.text_data.mask = Some(Mask::Char('*'));
+    if let InputOutcome::Submitted(password) = my_input.listen()? {
+        println!(
+            "your password is {} characters long",
+            password.chars().count()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use cool_rust_input::{Confirm, MultiSelect, Select};
+
+fn main() -> Result<(), std::io::Error> {
+    let colors = vec!["red", "green", "blue", "yellow", "purple"];
+    let favorite = Select::new("Favorite color?", colors.clone()).ask()?;
+    let toppings = MultiSelect::new("Pick toppings (space to toggle):", colors)
+        .min_selected(1)
+        .ask()?;
+    let sure = Confirm::new("Are you sure?", false).ask()?;
+    println!("favorite: {favorite:?}, toppings: {toppings:?}, sure: {sure:?}");
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use cool_rust_input::{
-    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputTransform,
-    KeyPressResult,
+    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputOutcome, InputTransform,
+    KeyPressResult, TextInputData,
 };
 use crossterm::{
     event::{Event, KeyCode},
@@ -24,21 +24,31 @@ impl CustomInputHandler for MyHandler {
         let _ = set_terminal_line(">", 0, 2, false);
     }
     fn handle_key_press(&mut self, key: &Event, _: HandlerContext) -> KeyPressResult {
-        // Make Enter stop the input
+        // Make Enter submit the input, and Escape cancel it
         if let Event::Key(key_event) = key {
             if key_event.kind == crossterm::event::KeyEventKind::Press {
-                if let KeyCode::Enter = key_event.code {
-                    return KeyPressResult::Stop;
+                match key_event.code {
+                    KeyCode::Enter => return KeyPressResult::Submit,
+                    KeyCode::Esc => return KeyPressResult::Stop,
+                    _ => {}
                 }
             }
         }
         KeyPressResult::Continue
     }
+    fn validate(&mut self, text_data: &TextInputData) -> Result<(), String> {
+        if text_data.text.trim().is_empty() {
+            return Err("please type a command".to_string());
+        }
+        Ok(())
+    }
 }
 
 fn main() -> Result<(), std::io::Error> {
     let mut my_input = CoolInput::new(MyHandler, 0);
-    my_input.listen()?;
-    println!("your input was: '{}'", my_input.text_data.text);
+    match my_input.listen()? {
+        InputOutcome::Submitted(text) => println!("your input was: '{text}'"),
+        InputOutcome::Cancelled => println!("cancelled"),
+    }
     Ok(())
 }
@@ -0,0 +1,12 @@
+use cool_rust_input::{Date, DateInput, NumberInput};
+
+fn main() -> Result<(), std::io::Error> {
+    // Use Up and Down to change the values
+    let age = NumberInput::<u8>::new("Age:").max(150).ask()?;
+    let price = NumberInput::<f64>::new("Price:").min(0.0).step(0.5).ask()?;
+    let deadline = DateInput::new("Deadline:")
+        .min(Date::new(2000, 1, 1).unwrap())
+        .ask()?;
+    println!("age: {age:?}, price: {price:?}, deadline: {deadline:?}");
+    Ok(())
+}
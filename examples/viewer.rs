@@ -0,0 +1,33 @@
+use cool_rust_input::{
+    CoolInput, CustomInputHandler, HandlerContext, KeyPressResult, LineNumbers, PagedFile,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use std::env;
+
+/// A `less` like viewer. Quits on q or Escape.
+struct ViewerInput;
+impl CustomInputHandler for ViewerInput {
+    fn handle_key_press(&mut self, key: &Event, _: HandlerContext) -> KeyPressResult {
+        if let Event::Key(key_event) = key {
+            if key_event.kind == KeyEventKind::Press
+                && matches!(key_event.code, KeyCode::Char('q') | KeyCode::Esc)
+            {
+                return KeyPressResult::Stop;
+            }
+        }
+        KeyPressResult::Continue
+    }
+}
+
+fn main() -> Result<(), std::io::Error> {
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 2 {
+        println!("please specify a filename!");
+        return Ok(());
+    }
+    let mut cool_input = CoolInput::new(ViewerInput, 4);
+    cool_input.viewer = Some(PagedFile::open(&args[1])?);
+    cool_input.gutter.line_numbers = LineNumbers::Absolute;
+    cool_input.listen()?;
+    Ok(())
+}
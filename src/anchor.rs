@@ -0,0 +1,106 @@
+// Helpers to keep positions in the text anchored to the same characters as the text is edited.
+// Positions are (x, y) pairs of a character index and a line index, like the cursor.
+
+/// Update a position after `c` was inserted at (x, y).
+/// If `stick_right` is true, a position exactly at (x, y) is moved along with the inserted character.
+pub(crate) fn shift_on_insert(
+    pos: &mut (usize, usize),
+    c: char,
+    x: usize,
+    y: usize,
+    stick_right: bool,
+) {
+    let at_or_after = pos.1 == y && (pos.0 > x || (pos.0 == x && stick_right));
+    if c == '\n' {
+        if at_or_after {
+            *pos = (pos.0 - x, pos.1 + 1);
+        } else if pos.1 > y {
+            pos.1 += 1;
+        }
+    } else if at_or_after {
+        pos.0 += 1;
+    }
+}
+
+/// Update a position after the character before (x, y) was removed.
+/// `previous_line_length` is the length of line y - 1 before the removal, which is used when a line break was removed.
+pub(crate) fn shift_on_remove(
+    pos: &mut (usize, usize),
+    x: usize,
+    y: usize,
+    previous_line_length: usize,
+) {
+    if x > 0 {
+        if pos.1 == y && pos.0 >= x {
+            pos.0 -= 1;
+        }
+    } else if y > 0 {
+        if pos.1 == y {
+            *pos = (previous_line_length + pos.0, y - 1);
+        } else if pos.1 > y {
+            pos.1 -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_before_on_same_line() {
+        let mut pos = (3, 1);
+        shift_on_insert(&mut pos, 'a', 1, 1, false);
+        assert_eq!(pos, (4, 1));
+        shift_on_insert(&mut pos, 'a', 1, 0, false);
+        assert_eq!(pos, (4, 1));
+    }
+
+    #[test]
+    fn insert_at_position_sticks() {
+        let mut left = (2, 0);
+        let mut right = (2, 0);
+        shift_on_insert(&mut left, 'a', 2, 0, false);
+        shift_on_insert(&mut right, 'a', 2, 0, true);
+        assert_eq!(left, (2, 0));
+        assert_eq!(right, (3, 0));
+    }
+
+    #[test]
+    fn insert_line_break() {
+        let mut pos = (5, 1);
+        shift_on_insert(&mut pos, '\n', 2, 1, false);
+        assert_eq!(pos, (3, 2));
+        let mut below = (1, 3);
+        shift_on_insert(&mut below, '\n', 2, 1, false);
+        assert_eq!(below, (1, 4));
+    }
+
+    #[test]
+    fn remove_character() {
+        let mut pos = (4, 0);
+        shift_on_remove(&mut pos, 2, 0, 0);
+        assert_eq!(pos, (3, 0));
+        let mut before = (1, 0);
+        shift_on_remove(&mut before, 2, 0, 0);
+        assert_eq!(before, (1, 0));
+    }
+
+    #[test]
+    fn remove_line_break() {
+        let mut pos = (2, 1);
+        shift_on_remove(&mut pos, 0, 1, 5);
+        assert_eq!(pos, (7, 0));
+        let mut below = (2, 3);
+        shift_on_remove(&mut below, 0, 1, 5);
+        assert_eq!(below, (2, 2));
+    }
+
+    #[test]
+    fn insert_then_remove_restores() {
+        let mut pos = (3, 2);
+        shift_on_insert(&mut pos, '\n', 1, 2, false);
+        shift_on_remove(&mut pos, 0, 3, 1);
+        assert_eq!(pos, (3, 2));
+    }
+}
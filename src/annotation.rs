@@ -0,0 +1,104 @@
+use crate::gutter::Sign;
+use crate::highlight::Highlight;
+use crossterm::style::{Attribute, Color, ContentStyle};
+
+/// How severe an [Annotation] is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn color(&self) -> Color {
+        match self {
+            Severity::Hint => Color::DarkGrey,
+            Severity::Info => Color::Cyan,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Hint => "hint",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+    /// The [Sign] shown in the [gutter](crate::Gutter) for lines with an annotation of this severity
+    pub fn sign(&self) -> Sign {
+        match self {
+            Severity::Hint => Sign::new('H', self.color()),
+            Severity::Info => Sign::new('I', self.color()),
+            Severity::Warning => Sign::warning(),
+            Severity::Error => Sign::error(),
+        }
+    }
+}
+
+/// A message attached to a range of text, like a diagnostic from a validator.
+///
+/// Annotated text is underlined, and the message is shown in the [status row](crate::CoolInput::status_row) while the cursor is inside of the range.
+/// Annotations stay anchored to their text as it is edited.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Annotation {
+    /// Start position (x, y) of the range (inclusive)
+    pub start: (usize, usize),
+    /// End position (x, y) of the range (exclusive)
+    pub end: (usize, usize),
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Annotation {
+    pub fn new(
+        start: (usize, usize),
+        end: (usize, usize),
+        severity: Severity,
+        message: &str,
+    ) -> Self {
+        Annotation {
+            start,
+            end,
+            severity,
+            message: message.to_string(),
+        }
+    }
+    /// Whether the position (x, y) is inside of the annotated range, including its end
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        let pos = (y, x);
+        (self.start.1, self.start.0) <= pos && pos <= (self.end.1, self.end.0)
+    }
+    /// Whether the range covers no characters
+    pub fn is_empty(&self) -> bool {
+        (self.start.1, self.start.0) >= (self.end.1, self.end.0)
+    }
+    /// Get the highlight of the annotated part of a line
+    pub(crate) fn highlight_line(&self, line: usize, line_length: usize) -> Option<Highlight> {
+        if line < self.start.1 || line > self.end.1 {
+            return None;
+        }
+        let start = if line == self.start.1 {
+            self.start.0
+        } else {
+            0
+        };
+        let end = if line == self.end.1 {
+            self.end.0
+        } else {
+            line_length
+        };
+        let mut style = ContentStyle {
+            foreground_color: Some(self.severity.color()),
+            underline_color: Some(self.severity.color()),
+            ..ContentStyle::new()
+        };
+        style.attributes.set(Attribute::Underlined);
+        Some(Highlight::new(line, start, end, style))
+    }
+}
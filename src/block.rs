@@ -0,0 +1,364 @@
+use crate::cursors::Cursor;
+use crate::highlight::{layout_line, Highlight};
+use crate::TextInputData;
+use crossterm::event::KeyCode;
+use crossterm::style::ContentStyle;
+use std::cmp;
+use std::io::Result;
+
+/// A rectangular selection, going from the anchor to the cursor.
+///
+/// Positions are (x, y) like the cursor, but x is a screen column, where tabs reach to the next tab stop, and may be past the end of its line ("virtual space").
+/// Typing, Backspace and Delete apply to every line of the rectangle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlockSelection {
+    pub anchor: (usize, usize),
+    pub cursor: (usize, usize),
+}
+
+impl BlockSelection {
+    /// Get the first and last (exclusive) screen column of the rectangle
+    pub fn columns(&self) -> (usize, usize) {
+        (
+            cmp::min(self.anchor.0, self.cursor.0),
+            cmp::max(self.anchor.0, self.cursor.0),
+        )
+    }
+    /// Get the first and last (inclusive) line of the rectangle
+    pub fn lines(&self) -> (usize, usize) {
+        (
+            cmp::min(self.anchor.1, self.cursor.1),
+            cmp::max(self.anchor.1, self.cursor.1),
+        )
+    }
+}
+
+/// Text that was copied from a [TextInputData]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Clipboard {
+    Text(String),
+    /// The lines of a [block selection](BlockSelection). Pasted as a rectangle at the cursor.
+    Block(Vec<String>),
+}
+
+impl TextInputData {
+    // Get the length of a line, or 0 if it doesn't exist
+    pub(crate) fn line_length(&self, y: usize) -> usize {
+        self.get_line_at(y).map_or(0, |line| line.chars().count())
+    }
+    // Get the screen column of each character of a line, with one extra entry for the end of the line
+    fn line_columns(&self, y: usize) -> Vec<usize> {
+        layout_line(self.get_line_at(y).unwrap_or(""), &[], None, self.tab_width).columns
+    }
+    /// Get the screen column of character x of a line. Positions past the end of the line take one column each.
+    pub fn column_of(&self, x: usize, y: usize) -> usize {
+        let columns = self.line_columns(y);
+        let length = columns.len() - 1;
+        columns[cmp::min(x, length)] + x.saturating_sub(length)
+    }
+    /// Get the first character of a line at or after a screen column, and how many columns the line is too short to reach it
+    pub fn index_at_column(&self, column: usize, y: usize) -> (usize, usize) {
+        let columns = self.line_columns(y);
+        match columns.iter().position(|c| *c >= column) {
+            Some(x) => (x, 0),
+            None => (columns.len() - 1, column - columns[columns.len() - 1]),
+        }
+    }
+    /// Select a rectangle from `anchor` to `cursor`, which are (screen column, line). The main cursor is moved to the corner at `cursor`, as far as its line goes.
+    pub fn select_block(&mut self, anchor: (usize, usize), cursor: (usize, usize)) {
+        let last_line = self.get_amt_lines().saturating_sub(1);
+        let anchor = (anchor.0, cmp::min(anchor.1, last_line));
+        let cursor = (cursor.0, cmp::min(cursor.1, last_line));
+        self.block_selection = Some(BlockSelection { anchor, cursor });
+        self.selection_anchor = None;
+        self.extra_cursors.clear();
+        self.cursor_y = cursor.1;
+        self.cursor_x = self.index_at_column(cursor.0, cursor.1).0;
+    }
+    // Grow or shrink the block selection with an arrow key, starting one at the cursor if there is none
+    pub(crate) fn move_block_cursor(&mut self, code: KeyCode) {
+        let column = self.column_of(self.cursor_x, self.cursor_y);
+        let block = self.block_selection.unwrap_or(BlockSelection {
+            anchor: (column, self.cursor_y),
+            cursor: (column, self.cursor_y),
+        });
+        let (x, y) = block.cursor;
+        let cursor = match code {
+            KeyCode::Up => (x, y.saturating_sub(1)),
+            KeyCode::Down => (x, y + 1),
+            KeyCode::Left => (x.saturating_sub(1), y),
+            KeyCode::Right => (x + 1, y),
+            _ => (x, y),
+        };
+        self.select_block(block.anchor, cursor);
+    }
+    /// Get the text in each line of the block selection. Nothing can be copied while the text is [masked](TextInputData::mask).
+    pub fn get_block_text(&self) -> Option<Vec<String>> {
+        if self.mask.is_some() {
+            return None;
+        }
+        let block = self.block_selection?;
+        let (left, right) = block.columns();
+        let (top, bottom) = block.lines();
+        Some(
+            (top..=bottom)
+                .map(|y| {
+                    let start = self.index_at_column(left, y).0;
+                    let end = self.index_at_column(right, y).0;
+                    self.get_line_at(y)
+                        .unwrap_or("")
+                        .chars()
+                        .skip(start)
+                        .take(end - start)
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+    /// Replace the block selection with a cursor on each of its lines, at its left column.
+    /// The text in the rectangle is removed. If `pad` is true, as when typing, lines too short to reach the column are padded with spaces.
+    /// Otherwise the cursors of those lines are at their end.
+    ///
+    /// Returns whether any text was removed. If any of the text can't be removed, ex. it is [protected](TextInputData::protected_ranges),
+    /// nothing is changed and the block selection is kept.
+    pub fn block_to_cursors(&mut self, pad: bool) -> Result<bool> {
+        let Some(block) = self.block_selection else {
+            return Ok(false);
+        };
+        let (left, right) = block.columns();
+        let (top, bottom) = block.lines();
+        let rejected = (top..=bottom).find(|y| {
+            let start = self.index_at_column(left, *y).0;
+            let end = self.index_at_column(right, *y).0;
+            start < end && !self.can_remove_between((start, *y), (end, *y))
+        });
+        if let Some(y) = rejected {
+            self.reject_edit(self.index_at_column(left, y).0, y);
+            return Ok(false);
+        }
+        self.block_selection = None;
+        self.selection_anchor = None;
+        self.extra_cursors.clear();
+
+        let mut removed = false;
+        for y in top..=bottom {
+            let (start, short) = self.index_at_column(left, y);
+            let end = self.index_at_column(right, y).0;
+            if start < end {
+                removed |= self.remove_between((start, y), (end, y))?;
+            } else if pad {
+                for x in start..start + short {
+                    self.insert_char(' ', x, y);
+                }
+            }
+        }
+        let mut cursors: Vec<Cursor> = (top..=bottom)
+            .map(|y| Cursor::new(self.index_at_column(left, y).0, y))
+            .collect();
+        self.set_main_cursor(cursors.remove(0));
+        self.extra_cursors = cursors;
+        Ok(removed)
+    }
+    /// Insert the lines of a block at the cursor, one below the other, starting at the cursor's screen column.
+    /// Lines are added to the end of the text when needed, and short lines are padded with spaces.
+    pub fn insert_block(&mut self, lines: &[String]) {
+        let (column, top) = (self.column_of(self.cursor_x, self.cursor_y), self.cursor_y);
+        let mut end = (self.cursor_x, top);
+        for (i, line) in lines.iter().enumerate() {
+            let y = top + i;
+            let amt_lines = cmp::max(self.get_amt_lines(), 1);
+            if y >= amt_lines {
+                let last_line = amt_lines - 1;
+                if !self.insert_char('\n', self.line_length(last_line), last_line) {
+                    break;
+                }
+            }
+            let (x, short) = self.index_at_column(column, y);
+            for pad_x in x..x + short {
+                self.insert_char(' ', pad_x, y);
+            }
+            let mut line_x = x + short;
+            for c in line.chars() {
+                if self.insert_char(c, line_x, y) {
+                    line_x += 1;
+                }
+            }
+            end = (line_x, y);
+        }
+        self.cursor_y = end.1;
+        self.cursor_x = cmp::min(end.0, self.line_length(end.1));
+    }
+    /// Copy the block selection, or else the main cursor's selection, to the [clipboard](TextInputData::clipboard).
+    /// Returns whether anything was copied.
+    pub fn copy(&mut self) -> bool {
+        let copied = if self.block_selection.is_some() {
+            self.get_block_text().map(Clipboard::Block)
+        } else {
+            self.get_selected_text()
+                .map(|text| Clipboard::Text(text.to_string()))
+        };
+        match copied {
+            Some(copied) => {
+                self.clipboard = Some(copied);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Copy and remove the block selection, or else the main cursor's selection
+    pub fn cut(&mut self) -> Result<()> {
+        if !self.copy() {
+            return Ok(());
+        }
+        if self.block_selection.is_some() {
+            self.block_to_cursors(false)?;
+        } else {
+            self.delete_selection()?;
+        }
+        Ok(())
+    }
+    /// Paste the [clipboard](TextInputData::clipboard), replacing what is selected
+    pub fn paste(&mut self) -> Result<()> {
+        let Some(clipboard) = self.clipboard.clone() else {
+            return Ok(());
+        };
+        self.block_to_cursors(true)?;
+        if self.block_selection.is_some() {
+            return Ok(());
+        }
+        match clipboard {
+            Clipboard::Text(text) => self.for_each_cursor(|text_data| {
+                if !text_data.delete_selection_for_insert()? {
+                    return Ok(());
+                }
+                text_data.write_str(&text)
+            }),
+            Clipboard::Block(lines) => {
+                self.extra_cursors.clear();
+                if !self.delete_selection_for_insert()? {
+                    return Ok(());
+                }
+                self.insert_block(&lines);
+                Ok(())
+            }
+        }
+    }
+    /// Get highlights for the block selection
+    pub(crate) fn get_block_highlights(
+        &self,
+        selection_style: ContentStyle,
+        cursor_style: ContentStyle,
+    ) -> Vec<Highlight> {
+        let Some(block) = self.block_selection else {
+            return Vec::new();
+        };
+        let (left, right) = block.columns();
+        let (top, bottom) = block.lines();
+        (top..=bottom)
+            .filter_map(|y| {
+                let start = self.index_at_column(left, y).0;
+                let end = self.index_at_column(right, y).0;
+                if left < right {
+                    Some(Highlight::new(y, start, end, selection_style))
+                } else if y != self.cursor_y {
+                    // Show where typing will insert on the other lines
+                    Some(Highlight::new(y, start, start + 1, cursor_style))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    fn text_data(text: &str) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(text);
+        text_data
+    }
+
+    fn press(text_data: &mut TextInputData, code: KeyCode) {
+        text_data
+            .handle_key_press(KeyEvent::new(code, KeyModifiers::NONE))
+            .unwrap();
+    }
+
+    #[test]
+    fn copy_block() {
+        let mut text_data = text_data("abcd\nef\nghij");
+        text_data.select_block((1, 0), (3, 2));
+        assert!(text_data.copy());
+        assert_eq!(
+            text_data.clipboard,
+            Some(Clipboard::Block(vec!["bc".into(), "f".into(), "hi".into()]))
+        );
+    }
+
+    #[test]
+    fn columns_count_tabs() {
+        let mut text_data = text_data("\tab\n12345x");
+        text_data.select_block((4, 0), (5, 1));
+        assert_eq!(
+            text_data.get_block_text(),
+            Some(vec!["a".to_string(), "5".to_string()])
+        );
+        assert_eq!(text_data.column_of(1, 0), 4);
+        assert_eq!(text_data.index_at_column(2, 0), (1, 0));
+        assert_eq!(text_data.index_at_column(8, 0), (3, 2));
+    }
+
+    #[test]
+    fn cut_and_paste_block() {
+        let mut text_data = text_data("abcd\nef\nghij");
+        text_data.select_block((1, 0), (3, 2));
+        text_data.cut().unwrap();
+        // Short lines aren't padded when nothing is inserted
+        assert_eq!(text_data.text, "ad\ne\ngj");
+        assert_eq!(text_data.extra_cursors.len(), 2);
+
+        text_data.extra_cursors.clear();
+        (text_data.cursor_x, text_data.cursor_y) = (1, 0);
+        text_data.paste().unwrap();
+        assert_eq!(text_data.text, "abcd\nef\nghij");
+
+        // Lines too short for the column are padded, and missing lines are added
+        (text_data.cursor_x, text_data.cursor_y) = (2, 1);
+        text_data.paste().unwrap();
+        assert_eq!(text_data.text, "abcd\nefbc\nghfij\n  hi");
+    }
+
+    #[test]
+    fn typing_into_block() {
+        let mut text_data = text_data("abcd\nef\ng");
+        text_data.select_block((3, 0), (3, 2));
+        press(&mut text_data, KeyCode::Char('|'));
+        assert_eq!(text_data.text, "abc|d\nef |\ng  |");
+        assert_eq!(text_data.block_selection, None);
+    }
+
+    #[test]
+    fn deleting_block_doesnt_pad() {
+        let mut text_data = text_data("abcd\nef\ng");
+        text_data.select_block((1, 0), (3, 2));
+        press(&mut text_data, KeyCode::Delete);
+        assert_eq!(text_data.text, "ad\ne\ng");
+    }
+
+    #[test]
+    fn protected_block_is_kept() {
+        let mut text_data = text_data("abcd\nef");
+        text_data.protected_ranges.push(((0, 1), (2, 1)));
+        text_data.select_block((1, 0), (2, 1));
+        press(&mut text_data, KeyCode::Backspace);
+        assert_eq!(text_data.text, "abcd\nef");
+        assert!(text_data.block_selection.is_some());
+        assert_eq!(text_data.take_rejected_edit(), Some((1, 1)));
+    }
+}
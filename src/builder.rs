@@ -0,0 +1,212 @@
+use crate::{CoolInput, CustomInputHandler, InputConstraints, Keymap, ScreenMode};
+use std::fmt::{self, Display};
+
+/// Where the cursor starts in the initial text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum CursorPlacement {
+    #[default]
+    Start,
+    End,
+    Position(usize, usize),
+}
+
+/// Error from [building](CoolInputBuilder::build) an input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The cursor position (x, y) isn't in the initial text
+    CursorOutOfRange((usize, usize)),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::CursorOutOfRange((x, y)) => {
+                write!(f, "cursor position ({x}, {y}) is outside the text")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Configures a [CoolInput] before it is created, instead of setting its fields afterwards.
+///
+/// ```no_run
+/// # use cool_rust_input::{CoolInputBuilder, DefaultInputHandler, InputConstraints};
+/// let mut input = CoolInputBuilder::new(DefaultInputHandler)
+///     .text("Subject: ")
+///     .cursor_at_end()
+///     .placeholder("Write a message")
+///     .constraints(InputConstraints::default().max_lines(20))
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct CoolInputBuilder<H: CustomInputHandler> {
+    handler: H,
+    text: String,
+    cursor: CursorPlacement,
+    tab_width: usize,
+    hard_tabs: bool,
+    screen_mode: ScreenMode,
+    mouse_capture: bool,
+    keymap: Keymap,
+    placeholder: Option<String>,
+    constraints: InputConstraints,
+    read_only: bool,
+}
+
+impl<H: CustomInputHandler> CoolInputBuilder<H> {
+    /// Start configuring an input with a handler. By default it has no text, a tab width of 4 and captures the mouse.
+    pub fn new(handler: H) -> Self {
+        CoolInputBuilder {
+            handler,
+            text: String::new(),
+            cursor: CursorPlacement::default(),
+            tab_width: 4,
+            hard_tabs: false,
+            screen_mode: ScreenMode::default(),
+            mouse_capture: true,
+            keymap: Keymap::default(),
+            placeholder: None,
+            constraints: InputConstraints::default(),
+            read_only: false,
+        }
+    }
+    /// Set the initial text. Its [line ending](crate::LineEnding) is detected and kept.
+    pub fn text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+    /// Start with the cursor at the start of the text (the default)
+    pub fn cursor_at_start(mut self) -> Self {
+        self.cursor = CursorPlacement::Start;
+        self
+    }
+    /// Start with the cursor at the end of the text
+    pub fn cursor_at_end(mut self) -> Self {
+        self.cursor = CursorPlacement::End;
+        self
+    }
+    /// Start with the cursor at (x, y). [build](CoolInputBuilder::build) fails if the position isn't in the text.
+    pub fn cursor_at(mut self, x: usize, y: usize) -> Self {
+        self.cursor = CursorPlacement::Position(x, y);
+        self
+    }
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+    /// Insert tab characters instead of spaces when pressing Tab or indenting
+    pub fn hard_tabs(mut self, hard_tabs: bool) -> Self {
+        self.hard_tabs = hard_tabs;
+        self
+    }
+    pub fn screen_mode(mut self, screen_mode: ScreenMode) -> Self {
+        self.screen_mode = screen_mode;
+        self
+    }
+    /// Capture mouse events while listening. Enabled by default.
+    pub fn mouse_capture(mut self, mouse_capture: bool) -> Self {
+        self.mouse_capture = mouse_capture;
+        self
+    }
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+    /// Text drawn dimmed while the text is empty
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = Some(placeholder.to_string());
+        self
+    }
+    pub fn constraints(mut self, constraints: InputConstraints) -> Self {
+        self.constraints = constraints;
+        self
+    }
+    /// Reject every edit, while moving the cursor, selecting and copying still work
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+    /// Create the input, checking that the cursor position is in the initial text
+    pub fn build(self) -> Result<CoolInput<H>, BuildError> {
+        let mut input = CoolInput::new(self.handler, self.tab_width);
+        let text_data = &mut input.text_data;
+        text_data.load_text(&self.text);
+        text_data.hard_tabs = self.hard_tabs;
+        text_data.constraints = self.constraints;
+        text_data.read_only = self.read_only;
+        let last_line = text_data.get_amt_lines().saturating_sub(1);
+        (text_data.cursor_x, text_data.cursor_y) = match self.cursor {
+            CursorPlacement::Start => (0, 0),
+            CursorPlacement::End => (text_data.line_length(last_line), last_line),
+            CursorPlacement::Position(x, y) => {
+                if y > last_line || x > text_data.line_length(y) {
+                    return Err(BuildError::CursorOutOfRange((x, y)));
+                }
+                (x, y)
+            }
+        };
+        input.screen_mode = self.screen_mode;
+        input.mouse_capture = self.mouse_capture;
+        input.keymap = self.keymap;
+        input.placeholder = self.placeholder;
+        Ok(input)
+    }
+}
+
+impl<H: CustomInputHandler> CoolInput<H> {
+    /// Start [configuring](CoolInputBuilder) an input with a handler
+    pub fn builder(handler: H) -> CoolInputBuilder<H> {
+        CoolInputBuilder::new(handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultInputHandler;
+
+    #[test]
+    fn cursor_placement() {
+        let input = CoolInputBuilder::new(DefaultInputHandler)
+            .text("ab\ncde")
+            .cursor_at_end()
+            .build()
+            .unwrap();
+        assert_eq!((input.text_data.cursor_x, input.text_data.cursor_y), (3, 1));
+
+        let input = CoolInputBuilder::new(DefaultInputHandler)
+            .text("ab\ncde")
+            .cursor_at(2, 0)
+            .build()
+            .unwrap();
+        assert_eq!((input.text_data.cursor_x, input.text_data.cursor_y), (2, 0));
+
+        let error = CoolInputBuilder::new(DefaultInputHandler)
+            .text("ab")
+            .cursor_at(3, 0)
+            .build()
+            .err();
+        assert_eq!(error, Some(BuildError::CursorOutOfRange((3, 0))));
+    }
+
+    #[test]
+    fn options_are_applied() {
+        let input = CoolInput::builder(DefaultInputHandler)
+            .tab_width(2)
+            .hard_tabs(true)
+            .screen_mode(ScreenMode::Alternate)
+            .mouse_capture(false)
+            .placeholder("Type here")
+            .constraints(InputConstraints::default().max_lines(1))
+            .build()
+            .unwrap();
+        assert_eq!(input.text_data.tab_width, 2);
+        assert!(input.text_data.hard_tabs);
+        assert_eq!(input.screen_mode, ScreenMode::Alternate);
+        assert!(!input.mouse_capture);
+        assert_eq!(input.placeholder.as_deref(), Some("Type here"));
+        assert!(!input.text_data.constraints.allows('\n', ""));
+    }
+}
@@ -0,0 +1,153 @@
+/// Limits on the text of a [TextInputData](crate::TextInputData).
+///
+/// Constraints are checked on every insertion, including typing, pasting and [insert_char](crate::TextInputData::insert_char).
+/// Characters that would break them are rejected. Text set with [load_text](crate::TextInputData::load_text) isn't checked, as it replaces the text rather than editing it.
+#[derive(Default)]
+pub struct InputConstraints {
+    /// Maximum amount of characters in the text, not counting line breaks
+    pub max_chars: Option<usize>,
+    /// Maximum amount of lines in the text. Set to 1 for single line input, where Enter is rejected.
+    pub max_lines: Option<usize>,
+    /// Predicate that characters (other than line breaks) must pass to be inserted
+    pub char_filter: Option<Box<dyn Fn(char) -> bool>>,
+}
+
+impl InputConstraints {
+    /// Constraints for single line input
+    pub fn single_line() -> Self {
+        InputConstraints {
+            max_lines: Some(1),
+            ..Default::default()
+        }
+    }
+    /// Set the maximum amount of characters
+    pub fn max_chars(mut self, max_chars: usize) -> Self {
+        self.max_chars = Some(max_chars);
+        self
+    }
+    /// Set the maximum amount of lines
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = Some(max_lines);
+        self
+    }
+    /// Only allow characters that pass the filter
+    pub fn char_filter(mut self, filter: impl Fn(char) -> bool + 'static) -> Self {
+        self.char_filter = Some(Box::new(filter));
+        self
+    }
+    /// Whether `c` may be inserted into `text`
+    pub fn allows(&self, c: char, text: &str) -> bool {
+        self.allows_counted(c, &self.count(text))
+    }
+    // Count what the limits need to know about a text, skipping the work if there are no limits
+    pub(crate) fn count(&self, text: &str) -> TextCounts {
+        if self.max_chars.is_none() && self.max_lines.is_none() {
+            return TextCounts::default();
+        }
+        let lines = text.matches('\n').count() + 1;
+        TextCounts {
+            chars: text.chars().count() + 1 - lines,
+            lines,
+        }
+    }
+    // Whether `c` may be inserted into a text with these counts
+    pub(crate) fn allows_counted(&self, c: char, counts: &TextCounts) -> bool {
+        if c == '\n' {
+            if let Some(max_lines) = self.max_lines {
+                return counts.lines < max_lines;
+            }
+            return true;
+        }
+        if let Some(max_chars) = self.max_chars {
+            if counts.chars >= max_chars {
+                return false;
+            }
+        }
+        match &self.char_filter {
+            Some(filter) => filter(c),
+            None => true,
+        }
+    }
+}
+
+/// Amounts of characters (not counting line breaks) and lines in a text, so a long paste doesn't recount the text for every character
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct TextCounts {
+    chars: usize,
+    lines: usize,
+}
+
+impl TextCounts {
+    // Update the counts after `c` was inserted
+    pub(crate) fn add(&mut self, c: char) {
+        if c == '\n' {
+            self.lines += 1;
+        } else {
+            self.chars += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextInputData;
+
+    fn text_data(constraints: InputConstraints) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.constraints = constraints;
+        text_data
+    }
+
+    #[test]
+    fn max_chars() {
+        let constraints = InputConstraints::default().max_chars(3);
+        assert!(constraints.allows('a', "ab"));
+        assert!(!constraints.allows('a', "abc"));
+        // Line breaks don't count as characters
+        assert!(constraints.allows('a', "a\nb"));
+        assert!(constraints.allows('\n', "abc"));
+    }
+
+    #[test]
+    fn max_lines() {
+        let constraints = InputConstraints::single_line();
+        assert!(!constraints.allows('\n', ""));
+        assert!(constraints.allows('a', "abc"));
+        let constraints = InputConstraints::default().max_lines(2);
+        assert!(constraints.allows('\n', "a"));
+        assert!(!constraints.allows('\n', "a\nb"));
+    }
+
+    #[test]
+    fn char_filter() {
+        let constraints = InputConstraints::default().char_filter(|c| c.is_ascii_digit());
+        assert!(constraints.allows('1', ""));
+        assert!(!constraints.allows('a', ""));
+        assert!(constraints.allows('\n', ""));
+    }
+
+    #[test]
+    fn paste_stops_at_limits() {
+        let mut text_data = text_data(InputConstraints::default().max_chars(5).max_lines(2));
+        text_data.write_str("abc\ndef\nghi").unwrap();
+        assert_eq!(text_data.text, "abc\nde");
+        text_data.write_char('x').unwrap();
+        assert_eq!(text_data.text, "abc\nde");
+    }
+
+    #[test]
+    fn filtered_chars_are_skipped() {
+        let mut text_data = text_data(InputConstraints::default().char_filter(|c| c != ' '));
+        text_data.write_str("a b c").unwrap();
+        assert_eq!(text_data.text, "abc");
+        assert_eq!(text_data.cursor_x, 3);
+    }
+
+    #[test]
+    fn loaded_text_isnt_checked() {
+        let mut text_data = text_data(InputConstraints::single_line());
+        text_data.load_text("a\nb");
+        assert_eq!(text_data.text, "a\nb");
+    }
+}
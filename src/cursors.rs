@@ -0,0 +1,462 @@
+use crate::anchor;
+use crate::highlight::Highlight;
+use crate::TextInputData;
+use crossterm::style::{Attribute, Color, ContentStyle};
+use std::cmp;
+use std::io::Result;
+
+/// An extra cursor of a [TextInputData], used when editing with multiple cursors
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cursor {
+    pub x: usize,
+    pub y: usize,
+    /// Position where the cursor's selection starts
+    pub selection_anchor: Option<(usize, usize)>,
+}
+
+impl Cursor {
+    pub fn new(x: usize, y: usize) -> Self {
+        Cursor {
+            x,
+            y,
+            selection_anchor: None,
+        }
+    }
+    pub(crate) fn shift_on_insert(&mut self, c: char, x: usize, y: usize) {
+        let mut pos = (self.x, self.y);
+        anchor::shift_on_insert(&mut pos, c, x, y, true);
+        (self.x, self.y) = pos;
+        if let Some(selection_anchor) = &mut self.selection_anchor {
+            anchor::shift_on_insert(selection_anchor, c, x, y, false);
+        }
+    }
+    pub(crate) fn shift_on_remove(&mut self, x: usize, y: usize, previous_line_length: usize) {
+        let mut pos = (self.x, self.y);
+        anchor::shift_on_remove(&mut pos, x, y, previous_line_length);
+        (self.x, self.y) = pos;
+        if let Some(selection_anchor) = &mut self.selection_anchor {
+            anchor::shift_on_remove(selection_anchor, x, y, previous_line_length);
+        }
+    }
+    // Get the start and end of the cursor's selection, which are both the cursor's position if nothing is selected
+    fn range(&self) -> ((usize, usize), (usize, usize)) {
+        order(
+            self.selection_anchor.unwrap_or((self.x, self.y)),
+            (self.x, self.y),
+        )
+    }
+    // Whether two cursors are in the same place, or their selections overlap
+    fn overlaps(&self, other: &Cursor) -> bool {
+        let (start, end) = self.range();
+        let (other_start, other_end) = other.range();
+        (self.x, self.y) == (other.x, other.y)
+            || (start, end) == (other_start, other_end)
+            || (key(start) < key(other_end) && key(other_start) < key(end))
+    }
+    // Grow the selection to also cover the other cursor's selection, keeping the direction of this one
+    fn merge(&mut self, other: &Cursor) {
+        let (start, end) = self.range();
+        let (other_start, other_end) = other.range();
+        let start = cmp::min_by_key(start, other_start, |p| key(*p));
+        let end = cmp::max_by_key(end, other_end, |p| key(*p));
+        if start == end {
+            return;
+        }
+        let backwards = self.selection_anchor.is_some() && (self.x, self.y) == self.range().0;
+        let (anchor, position) = if backwards {
+            (end, start)
+        } else {
+            (start, end)
+        };
+        (self.x, self.y) = position;
+        self.selection_anchor = Some(anchor);
+    }
+}
+
+// Compare positions (x, y) by where they are in the text
+pub(crate) fn key((x, y): (usize, usize)) -> (usize, usize) {
+    (y, x)
+}
+
+// Order two positions (x, y) by where they are in the text
+pub(crate) fn order(a: (usize, usize), b: (usize, usize)) -> ((usize, usize), (usize, usize)) {
+    if (a.1, a.0) <= (b.1, b.0) {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+// Whether a character is part of a word
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Highlight the text between two positions, which may be on different lines
+pub(crate) fn highlight_range(
+    text_data: &TextInputData,
+    start: (usize, usize),
+    end: (usize, usize),
+    style: ContentStyle,
+) -> Vec<Highlight> {
+    (start.1..=end.1)
+        .map(|y| {
+            let line_start = if y == start.1 { start.0 } else { 0 };
+            let line_end = if y == end.1 {
+                end.0
+            } else {
+                // Include the line break
+                text_data.get_line_at(y).map_or(0, |l| l.chars().count()) + 1
+            };
+            Highlight::new(y, line_start, line_end, style)
+        })
+        .collect()
+}
+
+impl TextInputData {
+    /// Get the main cursor as a [Cursor]
+    pub fn get_main_cursor(&self) -> Cursor {
+        Cursor {
+            x: self.cursor_x,
+            y: self.cursor_y,
+            selection_anchor: self.selection_anchor,
+        }
+    }
+    /// Move the main cursor
+    pub fn set_main_cursor(&mut self, cursor: Cursor) {
+        self.cursor_x = cursor.x;
+        self.cursor_y = cursor.y;
+        self.selection_anchor = cursor.selection_anchor;
+    }
+    /// Run an action once for every cursor. While the action runs, the cursor it runs for is the main cursor.
+    ///
+    /// Edits made by the action move the other cursors along with the text. Cursors that end up in the same place are merged.
+    pub fn for_each_cursor(
+        &mut self,
+        mut action: impl FnMut(&mut Self) -> Result<()>,
+    ) -> Result<()> {
+        if self.extra_cursors.is_empty() {
+            return action(self);
+        }
+        self.merge_cursors();
+        let mut cursors = vec![self.get_main_cursor()];
+        cursors.append(&mut self.extra_cursors);
+        let mut result = Ok(());
+        for i in 0..cursors.len() {
+            let cursor = cursors.remove(i);
+            self.extra_cursors = cursors;
+            self.set_main_cursor(cursor);
+            result = action(self);
+            cursors = std::mem::take(&mut self.extra_cursors);
+            cursors.insert(i, self.get_main_cursor());
+            if result.is_err() {
+                break;
+            }
+        }
+        let main = cursors.remove(0);
+        self.set_main_cursor(main);
+        self.extra_cursors = cursors;
+        self.merge_cursors();
+        result
+    }
+    /// Merge cursors that are in the same place or whose selections overlap, so no text is edited twice.
+    /// Merged selections cover both selections, and the main cursor stays the main cursor.
+    pub fn merge_cursors(&mut self) {
+        let mut cursors = vec![self.get_main_cursor()];
+        cursors.append(&mut self.extra_cursors);
+        loop {
+            let amt_cursors = cursors.len();
+            let mut merged: Vec<Cursor> = Vec::with_capacity(amt_cursors);
+            for cursor in cursors {
+                match merged.iter_mut().find(|other| other.overlaps(&cursor)) {
+                    Some(other) => other.merge(&cursor),
+                    None => merged.push(cursor),
+                }
+            }
+            cursors = merged;
+            // A merged selection may now overlap a cursor it was compared to before
+            if cursors.len() == amt_cursors {
+                break;
+            }
+        }
+        let main = cursors.remove(0);
+        self.set_main_cursor(main);
+        self.extra_cursors = cursors;
+    }
+    /// Add a cursor on the line above the topmost cursor, or below the bottommost cursor if `down` is true
+    pub fn add_cursor_vertically(&mut self, down: bool) -> Result<()> {
+        let main = self.get_main_cursor();
+        let edge = self
+            .extra_cursors
+            .iter()
+            .chain(std::iter::once(&main))
+            .max_by_key(|cursor| {
+                if down {
+                    cursor.y as isize
+                } else {
+                    -(cursor.y as isize)
+                }
+            })
+            .copied()
+            .unwrap_or(main);
+        let y = if down {
+            edge.y + 1
+        } else {
+            match edge.y.checked_sub(1) {
+                Some(y) => y,
+                None => return Ok(()),
+            }
+        };
+        if let Some(line) = self.get_line_at(y) {
+            let x = std::cmp::min(main.x, line.chars().count());
+            self.extra_cursors.push(Cursor::new(x, y));
+            self.merge_cursors();
+        }
+        Ok(())
+    }
+    /// Get the start and end of the main cursor's selection, if anything is selected
+    pub fn get_selection(&self) -> Option<((usize, usize), (usize, usize))> {
+        let anchor = self.selection_anchor?;
+        let cursor = (self.cursor_x, self.cursor_y);
+        if anchor == cursor {
+            return None;
+        }
+        Some(order(anchor, cursor))
+    }
+    /// Get the text between two positions
+    pub fn get_text_between(&self, start: (usize, usize), end: (usize, usize)) -> Option<&str> {
+        let start = self.get_byte_index(start.0, start.1)?;
+        let end = self.get_byte_index(end.0, end.1)?;
+        self.text.get(start..end)
+    }
+    /// Get the text selected by the main cursor. Nothing can be copied while the text is [masked](TextInputData::mask).
+    pub fn get_selected_text(&self) -> Option<&str> {
+        if self.mask.is_some() {
+            return None;
+        }
+        let (start, end) = self.get_selection()?;
+        self.get_text_between(start, end)
+    }
+    /// Delete the text selected by the main cursor. Returns whether there was a selection.
+    /// When the selection can't be removed, ex. it is [read-only](TextInputData::read_only) or [protected](TextInputData::protected_ranges), it is kept.
+    pub fn delete_selection(&mut self) -> Result<bool> {
+        let Some((start, end)) = self.get_selection() else {
+            self.selection_anchor = None;
+            return Ok(false);
+        };
+        if self.remove_between(start, end)? {
+            self.selection_anchor = None;
+        }
+        Ok(true)
+    }
+    // Delete the selection before inserting text over it. Returns false if it was kept, so nothing should be inserted.
+    pub(crate) fn delete_selection_for_insert(&mut self) -> Result<bool> {
+        Ok(!self.delete_selection()? || self.selection_anchor.is_none())
+    }
+    /// Remove the text between two positions, leaving the cursor at the start. Returns whether it was removed.
+    /// Nothing is removed if any of it is [read-only](TextInputData::read_only) or [protected](TextInputData::protected_ranges).
+    pub fn remove_between(&mut self, start: (usize, usize), end: (usize, usize)) -> Result<bool> {
+        let amt = match self.get_text_between(start, end) {
+            Some(text) => text.chars().count(),
+            None => return Ok(false),
+        };
+        if amt > 0 && !self.can_remove_between(start, end) {
+            self.reject_edit(start.0, start.1);
+            return Ok(false);
+        }
+        (self.cursor_x, self.cursor_y) = end;
+        for _ in 0..amt {
+            self.remove_character(self.cursor_x, self.cursor_y)?;
+        }
+        Ok(true)
+    }
+    /// Get the position (x, y) of a byte index in the text
+    pub fn get_position_of_byte_index(&self, index: usize) -> (usize, usize) {
+        let before = &self.text[..index];
+        let y = before.matches('\n').count();
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (before[line_start..].chars().count(), y)
+    }
+    /// Select the word under the main cursor. Returns whether there was a word.
+    pub fn select_word(&mut self) -> bool {
+        let Some(line) = self.get_line_at(self.cursor_y) else {
+            return false;
+        };
+        let chars: Vec<char> = line.chars().collect();
+        let mut start = std::cmp::min(self.cursor_x, chars.len());
+        let mut end = start;
+        while start > 0 && is_word_char(chars[start - 1]) {
+            start -= 1;
+        }
+        while end < chars.len() && is_word_char(chars[end]) {
+            end += 1;
+        }
+        if start == end {
+            return false;
+        }
+        self.selection_anchor = Some((start, self.cursor_y));
+        self.cursor_x = end;
+        true
+    }
+    /// Add a cursor selecting the next occurrence of the main cursor's selection.
+    /// If nothing is selected, the word under the cursor is selected first.
+    pub fn add_next_occurrence(&mut self) -> Result<()> {
+        let Some((start, end)) = self.get_selection() else {
+            self.select_word();
+            return Ok(());
+        };
+        let Some(needle) = self.get_text_between(start, end).map(str::to_string) else {
+            return Ok(());
+        };
+        // Search after the most recently added cursor, wrapping around at the end of the text
+        let last = self
+            .extra_cursors
+            .last()
+            .copied()
+            .unwrap_or(self.get_main_cursor());
+        let from = self.get_byte_index(last.x, last.y).unwrap_or(0);
+        let found = self.text[from..]
+            .find(&needle)
+            .map(|i| i + from)
+            .or_else(|| self.text.find(&needle));
+        let Some(found_start) = found else {
+            return Ok(());
+        };
+        let occurrence_start = self.get_position_of_byte_index(found_start);
+        let occurrence_end = self.get_position_of_byte_index(found_start + needle.len());
+        let mut cursor = Cursor::new(occurrence_end.0, occurrence_end.1);
+        cursor.selection_anchor = Some(occurrence_start);
+        self.extra_cursors.push(cursor);
+        self.merge_cursors();
+        Ok(())
+    }
+    /// Get highlights for the selections, the block selection and the extra cursors
+    pub(crate) fn get_cursor_highlights(&self) -> Vec<Highlight> {
+        let selection_style = ContentStyle {
+            background_color: Some(Color::DarkBlue),
+            ..ContentStyle::new()
+        };
+        let mut cursor_style = ContentStyle::new();
+        cursor_style.attributes.set(Attribute::Reverse);
+
+        let mut highlights = self.get_block_highlights(selection_style, cursor_style);
+        for cursor in
+            std::iter::once(self.get_main_cursor()).chain(self.extra_cursors.iter().copied())
+        {
+            if let Some(selection_anchor) = cursor.selection_anchor {
+                let (start, end) = order(selection_anchor, (cursor.x, cursor.y));
+                highlights.extend(highlight_range(self, start, end, selection_style));
+            }
+        }
+        for cursor in self.extra_cursors.iter() {
+            highlights.push(Highlight::new(
+                cursor.y,
+                cursor.x,
+                cursor.x + 1,
+                cursor_style,
+            ));
+        }
+        highlights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn text_data(text: &str) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(text);
+        text_data
+    }
+
+    fn selection(anchor: (usize, usize), cursor: (usize, usize)) -> Cursor {
+        Cursor {
+            x: cursor.0,
+            y: cursor.1,
+            selection_anchor: Some(anchor),
+        }
+    }
+
+    #[test]
+    fn cursors_in_the_same_place_are_merged() {
+        let mut text_data = text_data("abc\ndef");
+        text_data.extra_cursors = vec![Cursor::new(0, 0), Cursor::new(1, 1), Cursor::new(1, 1)];
+        text_data.merge_cursors();
+        assert_eq!(text_data.get_main_cursor(), Cursor::new(0, 0));
+        assert_eq!(text_data.extra_cursors, vec![Cursor::new(1, 1)]);
+    }
+
+    #[test]
+    fn overlapping_selections_are_merged() {
+        let mut text_data = text_data("hello world");
+        text_data.set_main_cursor(selection((0, 0), (5, 0)));
+        text_data.extra_cursors = vec![selection((8, 0), (3, 0)), selection((9, 0), (11, 0))];
+        text_data.merge_cursors();
+        // The main cursor keeps its direction and covers both selections
+        assert_eq!(text_data.get_main_cursor(), selection((0, 0), (8, 0)));
+        assert_eq!(text_data.extra_cursors, vec![selection((9, 0), (11, 0))]);
+    }
+
+    #[test]
+    fn touching_selections_stay_apart() {
+        let mut text_data = text_data("hello world");
+        text_data.set_main_cursor(selection((0, 0), (5, 0)));
+        text_data.extra_cursors = vec![selection((5, 0), (8, 0))];
+        text_data.merge_cursors();
+        assert_eq!(text_data.extra_cursors.len(), 1);
+    }
+
+    #[test]
+    fn typing_with_every_cursor() {
+        let mut text_data = text_data("a\nb\nc");
+        text_data.add_cursor_vertically(true).unwrap();
+        text_data.add_cursor_vertically(true).unwrap();
+        text_data
+            .handle_key_press(KeyEvent::new(KeyCode::Char('-'), KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(text_data.text, "-a\n-b\n-c");
+        assert_eq!(
+            text_data.extra_cursors,
+            vec![Cursor::new(1, 1), Cursor::new(1, 2)]
+        );
+    }
+
+    #[test]
+    fn backspace_merges_cursors_that_meet() {
+        let mut text_data = text_data("ab");
+        text_data.cursor_x = 1;
+        text_data.extra_cursors = vec![Cursor::new(2, 0)];
+        for _ in 0..2 {
+            text_data
+                .handle_key_press(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE))
+                .unwrap();
+        }
+        assert_eq!(text_data.text, "");
+        assert!(text_data.extra_cursors.is_empty());
+    }
+
+    #[test]
+    fn next_occurrence_selects_every_match() {
+        let mut text_data = text_data("foo bar foo\nfoo");
+        text_data.add_next_occurrence().unwrap();
+        assert_eq!(text_data.get_selected_text(), Some("foo"));
+        text_data.add_next_occurrence().unwrap();
+        text_data.add_next_occurrence().unwrap();
+        // Wraps around to the first one, which is already selected
+        text_data.add_next_occurrence().unwrap();
+        assert_eq!(
+            text_data.extra_cursors,
+            vec![selection((8, 0), (11, 0)), selection((0, 1), (3, 1))]
+        );
+        text_data
+            .for_each_cursor(|text_data| {
+                text_data.delete_selection()?;
+                text_data.write_str("x")
+            })
+            .unwrap();
+        assert_eq!(text_data.text, "x bar x\nx");
+    }
+}
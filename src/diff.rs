@@ -0,0 +1,190 @@
+/// How many edits `middle_snake` searches for before giving up on splitting a part
+const MAX_EDITS: isize = 4096;
+
+/// A line of a [diff](diff_lines) between two texts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    /// The line is in both texts
+    Same(&'a str),
+    /// The line is only in the old text
+    Removed(&'a str),
+    /// The line is only in the new text
+    Added(&'a str),
+}
+
+/// Compare two texts line by line, keeping as many lines in common as possible.
+///
+/// Uses Myers' diff algorithm in linear space, so large texts with many changes can be compared.
+/// Parts that differ in thousands of lines are shown as removed and added as a whole, instead of searching them for the few lines they may have in common.
+/// Within a changed part, removed lines come before added lines.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.split('\n').collect();
+    let new: Vec<&str> = new.split('\n').collect();
+    let mut diff = Vec::new();
+    diff_slices(&old, &new, &mut diff);
+
+    // Move removed lines before the added lines they are between
+    let mut start = 0;
+    while start < diff.len() {
+        let end = diff[start..]
+            .iter()
+            .position(|line| matches!(line, DiffLine::Same(_)))
+            .map_or(diff.len(), |i| start + i);
+        diff[start..end].sort_by_key(|line| matches!(line, DiffLine::Added(_)));
+        start = end + 1;
+    }
+    diff
+}
+
+// Diff two slices of lines, splitting them at the middle of their shortest edit script until what is left is only added or removed
+fn diff_slices<'a>(old: &[&'a str], new: &[&'a str], diff: &mut Vec<DiffLine<'a>>) {
+    // Lines at the start and end that didn't change don't need to be compared
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    diff.extend(old[..prefix].iter().map(|l| DiffLine::Same(l)));
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let split = match (old_middle.is_empty(), new_middle.is_empty()) {
+        (false, false) => middle_snake(old_middle, new_middle),
+        _ => None,
+    };
+    match split {
+        Some((x, y)) => {
+            diff_slices(&old_middle[..x], &new_middle[..y], diff);
+            diff_slices(&old_middle[x..], &new_middle[y..], diff);
+        }
+        None => {
+            diff.extend(old_middle.iter().map(|l| DiffLine::Removed(l)));
+            diff.extend(new_middle.iter().map(|l| DiffLine::Added(l)));
+        }
+    }
+    diff.extend(old[old.len() - suffix..].iter().map(|l| DiffLine::Same(l)));
+}
+
+// Find where a shortest edit script between two slices crosses its middle, by searching from both ends at once.
+// Returns `None` if the slices can't be split into smaller parts, or differ in too many lines to search.
+fn middle_snake(old: &[&str], new: &[&str]) -> Option<(usize, usize)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2 + 1;
+    // Furthest x reached on each diagonal k = x - y, from the start and from the end
+    let mut forward = vec![0_isize; 2 * max as usize + 1];
+    let mut backward = vec![0_isize; 2 * max as usize + 1];
+    let at = |k: isize| (k + max) as usize;
+
+    for d in 0..max.min(MAX_EDITS) {
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x_start, y_start) = (x, y);
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            if odd && (k - delta).abs() < d && x + backward[at(delta - k)] >= n {
+                return split_at(x_start, y_start, n, m);
+            }
+        }
+        for k in (-d..=d).rev().step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[(n - x - 1) as usize] == new[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if !odd && (k - delta).abs() <= d && x + forward[at(delta - k)] >= n {
+                return split_at(n - x, m - y, n, m);
+            }
+        }
+    }
+    None
+}
+
+// Only split at points that leave something on both sides, so the recursion always gets smaller
+fn split_at(x: isize, y: isize, n: isize, m: isize) -> Option<(usize, usize)> {
+    let at_edge = (x, y) == (0, 0) || (x, y) == (n, m);
+    (!at_edge).then_some((x as usize, y as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiffLine::*;
+
+    #[test]
+    fn same_text() {
+        assert_eq!(diff_lines("a\nb", "a\nb"), vec![Same("a"), Same("b")]);
+    }
+
+    #[test]
+    fn changed_line() {
+        assert_eq!(
+            diff_lines("a\nb\nc", "a\nx\nc"),
+            vec![Same("a"), Removed("b"), Added("x"), Same("c")]
+        );
+    }
+
+    #[test]
+    fn added_and_removed_lines() {
+        assert_eq!(
+            diff_lines("a\nb\nc\nd", "b\nc\ne\nd"),
+            vec![Removed("a"), Same("b"), Same("c"), Added("e"), Same("d")]
+        );
+    }
+
+    // Rebuild the old or new text from a diff
+    fn rebuild(diff: &[DiffLine], new: bool) -> String {
+        let lines: Vec<&str> = diff
+            .iter()
+            .filter_map(|line| match *line {
+                Same(text) => Some(text),
+                Removed(text) if !new => Some(text),
+                Added(text) if new => Some(text),
+                _ => None,
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    #[test]
+    fn keeps_longest_common_lines() {
+        let old = "a\nb\nc\na\nb\nb\na";
+        let new = "c\nb\na\nb\na\nc";
+        let diff = diff_lines(old, new);
+        let same = diff.iter().filter(|line| matches!(line, Same(_))).count();
+        assert_eq!(same, 4);
+        assert_eq!(rebuild(&diff, false), old);
+        assert_eq!(rebuild(&diff, true), new);
+    }
+
+    #[test]
+    fn completely_different_texts() {
+        let old: String = (0..10_000).map(|i| format!("old {i}\n")).collect();
+        let new: String = (0..10_000).map(|i| format!("new {i}\n")).collect();
+        let diff = diff_lines(&old, &new);
+        assert_eq!(diff.len(), 20_001);
+        assert!(diff[..10_000].iter().all(|line| matches!(line, Removed(_))));
+        assert_eq!(diff[20_000], Same(""));
+    }
+}
@@ -0,0 +1,494 @@
+use crate::TextInputData;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Display};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::SystemTime;
+
+/// Encoding of a [Document] on disk
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    /// UTF-16 little endian, with a byte order mark
+    Utf16Le,
+    /// UTF-16 big endian, with a byte order mark
+    Utf16Be,
+    /// ISO 8859-1, used when a file isn't valid UTF-8
+    Latin1,
+}
+
+impl Encoding {
+    /// Detect the encoding of bytes from their byte order mark, falling back to Latin-1 if they aren't valid UTF-8
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            Encoding::Utf8Bom
+        } else if bytes.starts_with(&[0xFF, 0xFE]) {
+            Encoding::Utf16Le
+        } else if bytes.starts_with(&[0xFE, 0xFF]) {
+            Encoding::Utf16Be
+        } else if std::str::from_utf8(bytes).is_ok() {
+            Encoding::Utf8
+        } else {
+            Encoding::Latin1
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Latin1 => "Latin-1",
+        }
+    }
+    /// Decode bytes in this encoding, skipping the byte order mark
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, DocumentError> {
+        let invalid = || DocumentError::InvalidData(*self);
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).map_err(|_| invalid()),
+            Encoding::Utf8Bom => {
+                let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+                String::from_utf8(bytes.to_vec()).map_err(|_| invalid())
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let bytes = bytes.get(2..).unwrap_or_default();
+                if bytes.len() % 2 != 0 {
+                    return Err(invalid());
+                }
+                let units = bytes.chunks_exact(2).map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if *self == Encoding::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                });
+                char::decode_utf16(units)
+                    .collect::<Result<String, _>>()
+                    .map_err(|_| invalid())
+            }
+            Encoding::Latin1 => Ok(bytes.iter().map(|b| *b as char).collect()),
+        }
+    }
+    /// Encode text in this encoding, including the byte order mark
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, DocumentError> {
+        Ok(match self {
+            Encoding::Utf8 => text.as_bytes().to_vec(),
+            Encoding::Utf8Bom => [&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat(),
+            Encoding::Utf16Le => [0xFF, 0xFE]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+            Encoding::Utf16Be => [0xFE, 0xFF]
+                .into_iter()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(c).map_err(|_| DocumentError::Unencodable(*self, c)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Error from loading or saving a [Document]
+#[derive(Debug)]
+pub enum DocumentError {
+    Io(io::Error),
+    /// The bytes aren't valid in the encoding
+    InvalidData(Encoding),
+    /// A character of the text can't be written in the encoding
+    Unencodable(Encoding, char),
+    /// The document has no path to save to
+    NoPath,
+}
+
+impl Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::Io(error) => write!(f, "{error}"),
+            DocumentError::InvalidData(encoding) => write!(f, "invalid {} data", encoding.name()),
+            DocumentError::Unencodable(encoding, c) => {
+                write!(f, "{c:?} can't be written as {}", encoding.name())
+            }
+            DocumentError::NoPath => write!(f, "no file to save to"),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DocumentError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DocumentError {
+    fn from(error: io::Error) -> Self {
+        DocumentError::Io(error)
+    }
+}
+
+/// A file edited in a [TextInputData]. Keeps track of where the text came from, how it was encoded, and whether it was edited since it was saved.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Document {
+    /// Where the document is saved. `None` for documents that weren't read from or saved to a file yet.
+    pub path: Option<PathBuf>,
+    pub encoding: Encoding,
+    /// [Revision](TextInputData::revision) of the text when it was last loaded or saved
+    saved_revision: u64,
+    /// The file as it was when it was last loaded or saved
+    disk_state: Option<DiskState>,
+    /// Whether the user kept their text over a version of the file changed by something else
+    diverged: bool,
+}
+
+/// What the file of a [Document] looked like, to notice when something else changes it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct DiskState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+    /// Modification time and size of the file when it was last found to be modified, so it isn't read again until it changes again
+    modified_seen: Option<(Option<SystemTime>, u64)>,
+}
+
+impl DiskState {
+    fn new(metadata: &fs::Metadata, bytes: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        DiskState {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            hash: hasher.finish(),
+            modified_seen: None,
+        }
+    }
+}
+
+/// Whether the file of a [Document] was changed by something else, returned by [check_disk](Document::check_disk)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskChange {
+    Unchanged,
+    /// The file has other contents than when it was last loaded or saved
+    Modified,
+    /// The file doesn't exist anymore
+    Deleted,
+}
+
+impl Document {
+    /// Create a document for a file that doesn't exist yet
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Document {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Document::default()
+        }
+    }
+    /// Read a file into a [TextInputData], detecting its encoding and [line ending](crate::LineEnding)
+    pub fn open(
+        path: impl AsRef<Path>,
+        text_data: &mut TextInputData,
+    ) -> Result<Document, DocumentError> {
+        let mut document = Document {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Document::default()
+        };
+        document.reload(text_data)?;
+        Ok(document)
+    }
+    /// Read from a reader into a [TextInputData], detecting its encoding and [line ending](crate::LineEnding).
+    /// The document has no path until it is [saved as](Document::save_as) a file.
+    pub fn read_from(
+        mut reader: impl Read,
+        text_data: &mut TextInputData,
+    ) -> Result<Document, DocumentError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let encoding = Encoding::detect(&bytes);
+        text_data.load_text(&encoding.decode(&bytes)?);
+        Ok(Document {
+            encoding,
+            saved_revision: text_data.revision(),
+            ..Document::default()
+        })
+    }
+    /// Read the file again into a [TextInputData], replacing its text
+    pub fn reload(&mut self, text_data: &mut TextInputData) -> Result<(), DocumentError> {
+        let path = self.path.clone().ok_or(DocumentError::NoPath)?;
+        let bytes = fs::read(&path)?;
+        let encoding = Encoding::detect(&bytes);
+        text_data.load_text(&encoding.decode(&bytes)?);
+        self.encoding = encoding;
+        self.saved_revision = text_data.revision();
+        self.disk_state = Some(DiskState::new(&fs::metadata(&path)?, &bytes));
+        self.diverged = false;
+        Ok(())
+    }
+    /// Read the text of the file as it is now, without loading it. Line breaks are converted to `\n`.
+    pub fn read_disk_text(&self) -> Result<String, DocumentError> {
+        let path = self.path.as_ref().ok_or(DocumentError::NoPath)?;
+        let bytes = fs::read(path)?;
+        let text = Encoding::detect(&bytes).decode(&bytes)?;
+        Ok(crate::LineEnding::normalize(&text))
+    }
+    /// Check whether the file was changed by something else since it was last loaded or saved, ex. a formatter or `git checkout`.
+    ///
+    /// This is cheap while the file's modification time and size stay the same, so it can be called regularly from [on_tick](crate::CustomInputHandler::on_tick).
+    /// A file found to be modified isn't read again until it changes again.
+    pub fn check_disk(&mut self) -> io::Result<DiskChange> {
+        let Some(path) = &self.path else {
+            return Ok(DiskChange::Unchanged);
+        };
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(match self.disk_state {
+                    Some(_) => DiskChange::Deleted,
+                    None => DiskChange::Unchanged,
+                });
+            }
+            Err(error) => return Err(error),
+        };
+        let Some(disk_state) = &mut self.disk_state else {
+            // The file was created by something else
+            return Ok(DiskChange::Modified);
+        };
+        let seen = (metadata.modified().ok(), metadata.len());
+        if seen == (disk_state.modified, disk_state.len) {
+            return Ok(DiskChange::Unchanged);
+        }
+        if disk_state.modified_seen == Some(seen) {
+            return Ok(DiskChange::Modified);
+        }
+        // The file was touched, but it may still have the same contents
+        let new_state = DiskState::new(&metadata, &fs::read(path)?);
+        if new_state.hash == disk_state.hash {
+            *disk_state = new_state;
+            return Ok(DiskChange::Unchanged);
+        }
+        disk_state.modified_seen = Some(seen);
+        Ok(DiskChange::Modified)
+    }
+    /// Keep the user's text over the file changed by something else. The change isn't reported again, and the document counts as [dirty](Document::is_dirty) until it is saved.
+    pub fn keep_text(&mut self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.disk_state = match fs::read(path) {
+            Ok(bytes) => Some(DiskState::new(&fs::metadata(path)?, &bytes)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => return Err(error),
+        };
+        self.diverged = true;
+        Ok(())
+    }
+    /// Whether the text was edited since it was loaded or saved
+    pub fn is_dirty(&self, text_data: &TextInputData) -> bool {
+        self.diverged || text_data.revision() != self.saved_revision
+    }
+    /// Save the text to the document's path
+    pub fn save(&mut self, text_data: &TextInputData) -> Result<(), DocumentError> {
+        let path = self.path.clone().ok_or(DocumentError::NoPath)?;
+        self.save_as(path, text_data)
+    }
+    /// Save the text to a path, which becomes the document's path.
+    ///
+    /// The text is written to a temporary file next to the path first, which then replaces the file, so a failed save never leaves a half written file.
+    /// The permissions of the replaced file are kept, and symlinks are followed. Files with several hard links are written in place instead, so every link sees the new text.
+    pub fn save_as(
+        &mut self,
+        path: impl AsRef<Path>,
+        text_data: &TextInputData,
+    ) -> Result<(), DocumentError> {
+        let path = path.as_ref();
+        let bytes = self
+            .encoding
+            .encode(&text_data.get_text_with_line_endings())?;
+
+        // Write to the file a symlink points to, instead of replacing the symlink
+        let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let file_name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp_path = target.with_file_name(format!(".{file_name}.{}.tmp", process::id()));
+        let result = write_replacing(&target, &temp_path, &bytes);
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result?;
+
+        self.path = Some(path.to_path_buf());
+        self.saved_revision = text_data.revision();
+        self.disk_state = Some(DiskState::new(&fs::metadata(path)?, &bytes));
+        self.diverged = false;
+        Ok(())
+    }
+}
+
+// Create a file that only the current user can read and write, until its permissions are set
+pub(crate) fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+// Write bytes to a temporary file, then move it over the path
+fn write_replacing(path: &Path, temp_path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let metadata = fs::metadata(path).ok();
+    // Renaming would give the other names of a hard linked file the old contents, so write those in place
+    #[cfg(unix)]
+    if let Some(metadata) = &metadata {
+        if std::os::unix::fs::MetadataExt::nlink(metadata) > 1 {
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .open(path)?;
+            file.write_all(bytes)?;
+            return file.sync_all();
+        }
+    }
+    let mut file = create_private_file(temp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    if let Some(metadata) = &metadata {
+        fs::set_permissions(temp_path, metadata.permissions())?;
+    }
+    fs::rename(temp_path, path)?;
+    // Make the rename itself survive a crash
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        fs::File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A path in the temp directory that is unique to this test run
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cool-rust-input-{}-{name}", process::id()))
+    }
+
+    #[test]
+    fn detect_encoding() {
+        assert_eq!(Encoding::detect(b"plain"), Encoding::Utf8);
+        assert_eq!(Encoding::detect("h\u{e9}".as_bytes()), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"\xEF\xBB\xBFhi"), Encoding::Utf8Bom);
+        assert_eq!(Encoding::detect(b"\xFF\xFEh\0"), Encoding::Utf16Le);
+        assert_eq!(Encoding::detect(b"\xFE\xFF\0h"), Encoding::Utf16Be);
+        assert_eq!(Encoding::detect(b"h\xE9"), Encoding::Latin1);
+    }
+
+    #[test]
+    fn decode_skips_byte_order_mark() {
+        assert_eq!(Encoding::Utf8Bom.decode(b"\xEF\xBB\xBFhi").unwrap(), "hi");
+        assert_eq!(Encoding::Utf16Le.decode(b"\xFF\xFEh\0i\0").unwrap(), "hi");
+        assert_eq!(Encoding::Utf16Be.decode(b"\xFE\xFF\0h\0i").unwrap(), "hi");
+        assert_eq!(Encoding::Latin1.decode(b"h\xE9").unwrap(), "h\u{e9}");
+        assert!(Encoding::Utf16Le.decode(b"\xFF\xFEh").is_err());
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        let text = "h\u{e9}llo\n";
+        for encoding in [
+            Encoding::Utf8,
+            Encoding::Utf8Bom,
+            Encoding::Utf16Le,
+            Encoding::Utf16Be,
+            Encoding::Latin1,
+        ] {
+            let bytes = encoding.encode(text).unwrap();
+            assert_eq!(Encoding::detect(&bytes), encoding);
+            assert_eq!(encoding.decode(&bytes).unwrap(), text);
+        }
+        assert!(matches!(
+            Encoding::Latin1.encode("\u{20ac}"),
+            Err(DocumentError::Unencodable(Encoding::Latin1, '\u{20ac}'))
+        ));
+    }
+
+    #[test]
+    fn save_keeps_encoding_and_line_endings() {
+        let path = temp_path("save.txt");
+        fs::write(&path, Encoding::Utf16Le.encode("a\r\nb\r\n").unwrap()).unwrap();
+        let mut text_data = TextInputData::new(4);
+        let mut document = Document::open(&path, &mut text_data).unwrap();
+        assert_eq!(document.encoding, Encoding::Utf16Le);
+        assert_eq!(text_data.text, "a\nb\n");
+
+        text_data.write_char('x').unwrap();
+        assert!(document.is_dirty(&text_data));
+        document.save(&text_data).unwrap();
+        assert!(!document.is_dirty(&text_data));
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(Encoding::Utf16Le.decode(&bytes).unwrap(), "xa\r\nb\r\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_disk_reports_changes() {
+        let path = temp_path("check.txt");
+        fs::write(&path, "text").unwrap();
+        let mut text_data = TextInputData::new(4);
+        let mut document = Document::open(&path, &mut text_data).unwrap();
+        assert_eq!(document.check_disk().unwrap(), DiskChange::Unchanged);
+
+        text_data.write_char('x').unwrap();
+        document.save(&text_data).unwrap();
+        assert_eq!(document.check_disk().unwrap(), DiskChange::Unchanged);
+
+        fs::write(&path, "changed by someone else").unwrap();
+        assert_eq!(document.check_disk().unwrap(), DiskChange::Modified);
+        // Still modified the next time, without the file changing again
+        assert_eq!(document.check_disk().unwrap(), DiskChange::Modified);
+        assert_eq!(
+            document.read_disk_text().unwrap(),
+            "changed by someone else"
+        );
+
+        document.keep_text().unwrap();
+        assert_eq!(document.check_disk().unwrap(), DiskChange::Unchanged);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(document.check_disk().unwrap(), DiskChange::Deleted);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn save_keeps_symlinks() {
+        let target = temp_path("target.txt");
+        let link = temp_path("link.txt");
+        fs::write(&target, "old").unwrap();
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let mut text_data = TextInputData::new(4);
+        let mut document = Document::open(&link, &mut text_data).unwrap();
+        text_data.write_char('x').unwrap();
+        document.save(&text_data).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "xold");
+        fs::remove_file(&link).unwrap();
+        fs::remove_file(&target).unwrap();
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{CoolInput, CustomInputHandler, HandlerContext, TextInputData};
+use std::io::Result;
+
+/// A change to the text of a [TextInputData], passed to [on_change](CustomInputHandler::on_change).
+///
+/// Every edit replaces the text `removed` at `start` with `inserted`. Characters typed or deleted one after another are merged into a single edit
+/// until the edits are [taken](TextInputData::take_edits). [CoolInput] takes them after every event, so a paste is one edit but typing a word is one per key press.
+/// Positions are in the text as it was after the edits before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edit {
+    /// Position (x, y) where the change starts
+    pub start: (usize, usize),
+    /// Text that was removed from `start`
+    pub removed: String,
+    /// Text that was inserted at `start`
+    pub inserted: String,
+    pub cursor_before: (usize, usize),
+    pub cursor_after: (usize, usize),
+    /// [Revision](TextInputData::revision) of the text after the edit
+    pub revision: u64,
+}
+
+impl Edit {
+    /// End of the removed text, in the text before the edit
+    pub fn old_end(&self) -> (usize, usize) {
+        end_position(self.start, &self.removed)
+    }
+    /// End of the inserted text, in the text after the edit
+    pub fn new_end(&self) -> (usize, usize) {
+        end_position(self.start, &self.inserted)
+    }
+}
+
+// Get the position after `text` if it starts at `start`
+pub(crate) fn end_position(start: (usize, usize), text: &str) -> (usize, usize) {
+    match text.rfind('\n') {
+        Some(index) => (
+            text[index + 1..].chars().count(),
+            start.1 + text.matches('\n').count(),
+        ),
+        None => (start.0 + text.chars().count(), start.1),
+    }
+}
+
+/// An [Edit] that is still being recorded
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PendingEdit {
+    start: (usize, usize),
+    /// Characters removed before `start` by Backspace, in reverse order
+    removed_before: String,
+    /// Characters removed after `start` by Delete
+    removed_after: String,
+    inserted: String,
+    /// End of `inserted`, kept to merge typing without counting the inserted text again
+    new_end: (usize, usize),
+    cursor_before: (usize, usize),
+    cursor_after: Option<(usize, usize)>,
+    revision: u64,
+}
+
+impl PendingEdit {
+    fn finish(self, cursor: (usize, usize)) -> Edit {
+        let mut removed: String = self.removed_before.chars().rev().collect();
+        removed += &self.removed_after;
+        Edit {
+            start: self.start,
+            removed,
+            inserted: self.inserted,
+            cursor_before: self.cursor_before,
+            cursor_after: self.cursor_after.unwrap_or(cursor),
+            revision: self.revision,
+        }
+    }
+}
+
+impl TextInputData {
+    /// Take the [edits](Edit) made since the last call. [CoolInput] takes them after every event to pass them to [on_change](CustomInputHandler::on_change).
+    /// Taken edits are added to the [undo history](TextInputData::undo_history).
+    pub fn take_edits(&mut self) -> Vec<Edit> {
+        self.finish_edits();
+        std::mem::take(&mut self.finished_edits)
+    }
+    // End the edits that are still being recorded, adding them to the undo history as one step
+    pub(crate) fn finish_edits(&mut self) {
+        let edits = self.drain_pending_edits();
+        self.undo_history.push(edits.clone());
+        self.finished_edits.extend(edits);
+    }
+    // End the edits that are still being recorded, without adding them to the undo history
+    pub(crate) fn drain_pending_edits(&mut self) -> Vec<Edit> {
+        let cursor = (self.cursor_x, self.cursor_y);
+        self.pending_edits
+            .drain(..)
+            .map(|edit| edit.finish(cursor))
+            .collect()
+    }
+    // Start a new edit, ending the one before it
+    fn push_edit(&mut self, edit: PendingEdit) {
+        if let Some(last) = self.pending_edits.last_mut() {
+            last.cursor_after.get_or_insert(edit.cursor_before);
+        }
+        self.pending_edits.push(edit);
+    }
+    // Record that `c` was inserted at (x, y)
+    pub(crate) fn record_insert(&mut self, c: char, x: usize, y: usize) {
+        let new_end = if c == '\n' { (0, y + 1) } else { (x + 1, y) };
+        if let Some(last) = self.pending_edits.last_mut() {
+            if last.new_end == (x, y) && last.cursor_after.is_none() {
+                last.inserted.push(c);
+                last.new_end = new_end;
+                last.revision = self.revision;
+                return;
+            }
+        }
+        self.push_edit(PendingEdit {
+            start: (x, y),
+            inserted: c.to_string(),
+            new_end,
+            cursor_before: (self.cursor_x, self.cursor_y),
+            revision: self.revision,
+            ..PendingEdit::default()
+        });
+    }
+    // Record that `c` was removed from before (x, y), where `before` is the position of `c`
+    pub(crate) fn record_remove(
+        &mut self,
+        c: char,
+        before: (usize, usize),
+        (x, y): (usize, usize),
+        cursor_before: (usize, usize),
+    ) {
+        if let Some(last) = self.pending_edits.last_mut() {
+            if last.cursor_after.is_none() {
+                if !last.inserted.is_empty() && last.new_end == (x, y) {
+                    // Removing what was just typed
+                    last.inserted.pop();
+                    last.new_end = before;
+                    last.revision = self.revision;
+                    return;
+                }
+                if last.inserted.is_empty() && last.start == (x, y) {
+                    last.removed_before.push(c);
+                    last.start = before;
+                    last.new_end = before;
+                    last.revision = self.revision;
+                    return;
+                }
+                if last.inserted.is_empty() && last.start == before {
+                    last.removed_after.push(c);
+                    last.revision = self.revision;
+                    return;
+                }
+            }
+        }
+        self.push_edit(PendingEdit {
+            start: before,
+            removed_after: c.to_string(),
+            new_end: before,
+            cursor_before,
+            revision: self.revision,
+            ..PendingEdit::default()
+        });
+    }
+    // Record that the whole text was replaced
+    pub(crate) fn record_replace_all(&mut self, removed: String) {
+        self.push_edit(PendingEdit {
+            start: (0, 0),
+            removed_after: removed,
+            inserted: self.text.clone(),
+            new_end: end_position((0, 0), &self.text),
+            cursor_before: (self.cursor_x, self.cursor_y),
+            cursor_after: Some((0, 0)),
+            revision: self.revision,
+            ..PendingEdit::default()
+        });
+    }
+}
+
+impl<H: CustomInputHandler> CoolInput<H> {
+    // Pass the edits made while handling an event to the handler
+    pub(crate) fn notify_changes(&mut self) -> Result<()> {
+        let edits = self.text_data.take_edits();
+        if edits.is_empty() {
+            return Ok(());
+        }
+        let terminal_size = self.get_terminal_size()?;
+        let mut render = false;
+        for edit in edits.iter() {
+            render |= self.custom_input.on_change(
+                edit,
+                HandlerContext {
+                    text_data: &mut self.text_data,
+                    terminal_size: &terminal_size,
+                },
+            );
+        }
+        if render {
+            self.render()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_data(text: &str) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(text);
+        text_data.take_edits();
+        text_data
+    }
+
+    #[test]
+    fn typing_is_merged() {
+        let mut text_data = text_data("");
+        text_data.write_str("ab\nc").unwrap();
+        let edits = text_data.take_edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].start, (0, 0));
+        assert_eq!(edits[0].inserted, "ab\nc");
+        assert_eq!(edits[0].removed, "");
+        assert_eq!(edits[0].cursor_before, (0, 0));
+        assert_eq!(edits[0].cursor_after, (1, 1));
+        assert_eq!(edits[0].new_end(), (1, 1));
+        assert_eq!(edits[0].revision, text_data.revision());
+    }
+
+    #[test]
+    fn backspace_is_merged() {
+        let mut text_data = text_data("hello");
+        (text_data.cursor_x, text_data.cursor_y) = (5, 0);
+        for _ in 0..3 {
+            text_data
+                .remove_character(text_data.cursor_x, text_data.cursor_y)
+                .unwrap();
+        }
+        let edits = text_data.take_edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].start, (2, 0));
+        assert_eq!(edits[0].removed, "llo");
+        assert_eq!(edits[0].old_end(), (5, 0));
+        assert_eq!(edits[0].cursor_after, (2, 0));
+    }
+
+    #[test]
+    fn backspace_over_typing_shrinks_the_edit() {
+        let mut text_data = text_data("x");
+        (text_data.cursor_x, text_data.cursor_y) = (1, 0);
+        text_data.write_str("abc").unwrap();
+        text_data.remove_character(4, 0).unwrap();
+        let edits = text_data.take_edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].inserted, "ab");
+        assert_eq!(text_data.text, "xab");
+    }
+
+    #[test]
+    fn edits_are_not_merged_after_being_taken() {
+        let mut text_data = text_data("");
+        text_data.write_char('a').unwrap();
+        assert_eq!(text_data.take_edits().len(), 1);
+        text_data.write_char('b').unwrap();
+        let edits = text_data.take_edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].start, (1, 0));
+        assert_eq!(edits[0].inserted, "b");
+    }
+
+    #[test]
+    fn typing_elsewhere_starts_a_new_edit() {
+        let mut text_data = text_data("abc");
+        text_data.write_char('x').unwrap();
+        text_data.cursor_x = 4;
+        text_data.write_char('y').unwrap();
+        let edits = text_data.take_edits();
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].cursor_after, (4, 0));
+        assert_eq!((edits[1].start, edits[1].inserted.as_str()), ((4, 0), "y"));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{
+    prepare_terminal, restore_terminal, set_terminal_line, CoolInput, CustomInputHandler,
+    HandlerContext, InputOutcome, InputTransform, KeyPressResult,
+};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::{Attribute, ResetColor, SetAttribute};
+use std::collections::HashMap;
+use std::io::{stdout, Result};
+
+/// An [input](CoolInput) that can be shown as a field of a [Form].
+///
+/// Implemented for every [CoolInput], so fields with different [handlers](CustomInputHandler) can be mixed.
+pub trait FormInput {
+    fn handle_event(&mut self, event: Event) -> Result<()>;
+    /// Render the text and cursor
+    fn render(&mut self) -> Result<()>;
+    /// Render the text without the cursor
+    fn render_text(&mut self) -> Result<()>;
+    fn take_outcome(&mut self) -> Option<InputOutcome>;
+    /// Validate the text, keeping the error to show. Returns whether the text is valid.
+    fn validate(&mut self) -> bool;
+    fn text(&self) -> &str;
+}
+
+impl<H: CustomInputHandler> FormInput for CoolInput<H> {
+    fn handle_event(&mut self, event: Event) -> Result<()> {
+        CoolInput::handle_event(self, event)
+    }
+    fn render(&mut self) -> Result<()> {
+        CoolInput::render(self)
+    }
+    fn render_text(&mut self) -> Result<()> {
+        CoolInput::render_text(self)
+    }
+    fn take_outcome(&mut self) -> Option<InputOutcome> {
+        CoolInput::take_outcome(self)
+    }
+    fn validate(&mut self) -> bool {
+        CoolInput::validate(self)
+    }
+    fn text(&self) -> &str {
+        &self.text_data.text
+    }
+}
+
+/// How a [Form] finished
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FormOutcome {
+    /// The form was submitted with valid fields. Maps the name of each field to its text.
+    Submitted(HashMap<String, String>),
+    /// The form was cancelled
+    Cancelled,
+}
+
+/// Several [inputs](CoolInput) shown on one screen, each with its own text, transform and handler.
+///
+/// Tab and Shift + Tab move the focus between fields, and only the focused field shows its cursor.
+/// When a field is [submitted](KeyPressResult::Submit) focus moves to the next field, and submitting the last field submits the form.
+/// CTRL + S submits the form from any field.
+pub struct Form {
+    fields: Vec<(String, Box<dyn FormInput>)>,
+    /// Index of the focused field
+    pub focused: usize,
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Form {
+    pub fn new() -> Self {
+        Form {
+            fields: Vec::new(),
+            focused: 0,
+        }
+    }
+    /// Add a field to the form. The field's text is returned under `name` when the form is submitted.
+    pub fn field<H: CustomInputHandler + 'static>(
+        mut self,
+        name: &str,
+        input: CoolInput<H>,
+    ) -> Self {
+        self.add_field(name, input);
+        self
+    }
+    /// Add a field to the form. The field's text is returned under `name` when the form is submitted.
+    pub fn add_field<H: CustomInputHandler + 'static>(&mut self, name: &str, input: CoolInput<H>) {
+        self.fields.push((name.to_string(), Box::new(input)));
+    }
+    /// Get the field with a name
+    pub fn get_field(&mut self, name: &str) -> Option<&mut dyn FormInput> {
+        self.fields
+            .iter_mut()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, field)| field.as_mut() as &mut dyn FormInput)
+    }
+    /// Render every field. The focused field is rendered last, so its cursor is the one shown.
+    pub fn render(&mut self) -> Result<()> {
+        for (i, (_, field)) in self.fields.iter_mut().enumerate() {
+            if i != self.focused {
+                field.render_text()?;
+            }
+        }
+        if let Some((_, field)) = self.fields.get_mut(self.focused) {
+            field.render()?;
+        }
+        Ok(())
+    }
+    fn focus(&mut self, index: usize) {
+        if !self.fields.is_empty() {
+            self.focused = index % self.fields.len();
+        }
+    }
+    /// Validate every field and collect their texts. Focuses the first invalid field if there is one.
+    fn submit(&mut self) -> Option<HashMap<String, String>> {
+        let mut first_invalid = None;
+        for (i, (_, field)) in self.fields.iter_mut().enumerate() {
+            if !field.validate() && first_invalid.is_none() {
+                first_invalid = Some(i);
+            }
+        }
+        if let Some(index) = first_invalid {
+            self.focus(index);
+            return None;
+        }
+        Some(
+            self.fields
+                .iter()
+                .map(|(name, field)| (name.clone(), field.text().to_string()))
+                .collect(),
+        )
+    }
+    /// Handle an event. Returns the outcome of the form if it finished.
+    pub fn handle_event(&mut self, event: Event) -> Result<Option<FormOutcome>> {
+        if self.fields.is_empty() {
+            return Ok(Some(FormOutcome::Cancelled));
+        }
+        if let Event::Key(key_event) = &event {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Tab => {
+                        self.focus(self.focused + 1);
+                        return Ok(None);
+                    }
+                    KeyCode::BackTab => {
+                        self.focus(self.focused + self.fields.len() - 1);
+                        return Ok(None);
+                    }
+                    KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Ok(self.submit().map(FormOutcome::Submitted));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let field = &mut self.fields[self.focused].1;
+        field.handle_event(event)?;
+        match field.take_outcome() {
+            Some(InputOutcome::Submitted(_)) if self.focused + 1 < self.fields.len() => {
+                self.focus(self.focused + 1);
+            }
+            Some(InputOutcome::Submitted(_)) => {
+                return Ok(self.submit().map(FormOutcome::Submitted));
+            }
+            Some(InputOutcome::Cancelled) => return Ok(Some(FormOutcome::Cancelled)),
+            None => {}
+        }
+        Ok(None)
+    }
+    /// Prepare the terminal and listen for key presses until the form is submitted or cancelled
+    pub fn listen(&mut self) -> Result<FormOutcome> {
+        prepare_terminal()?;
+        self.render()?;
+        let outcome = loop {
+            if let Some(outcome) = self.handle_event(event::read()?)? {
+                break outcome;
+            }
+            self.render()?;
+        };
+        restore_terminal()?;
+        Ok(outcome)
+    }
+}
+
+/// An input handler for a [Form] field: a label, followed by an input area at a fixed position.
+///
+/// Single line fields submit on Enter, while taller fields insert line breaks. Escape cancels the form.
+pub struct LabeledInput {
+    pub label: String,
+    /// Position of the label. The input area starts after it.
+    pub position: (u16, u16),
+    pub size: (u16, u16),
+}
+
+impl LabeledInput {
+    pub fn new(label: &str, position: (u16, u16), size: (u16, u16)) -> Self {
+        LabeledInput {
+            label: label.to_string(),
+            position,
+            size,
+        }
+    }
+}
+
+impl CustomInputHandler for LabeledInput {
+    fn handle_key_press(&mut self, key: &Event, _: HandlerContext) -> KeyPressResult {
+        if let Event::Key(key_event) = key {
+            if key_event.kind == KeyEventKind::Press {
+                match key_event.code {
+                    KeyCode::Esc => return KeyPressResult::Stop,
+                    KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return KeyPressResult::Stop;
+                    }
+                    KeyCode::Enter if self.size.1 <= 1 => return KeyPressResult::Submit,
+                    _ => {}
+                }
+            }
+        }
+        KeyPressResult::Continue
+    }
+    fn after_draw_text(&mut self, _: HandlerContext) {
+        let _ = queue!(stdout(), ResetColor, SetAttribute(Attribute::Bold));
+        let _ = set_terminal_line(
+            &self.label,
+            self.position.0 as usize,
+            self.position.1 as usize,
+            false,
+        );
+        let _ = queue!(stdout(), SetAttribute(Attribute::Reset));
+    }
+    fn get_input_transform(&mut self, _: HandlerContext) -> InputTransform {
+        let label_width = self.label.chars().count() as u16 + 1;
+        InputTransform {
+            size: self.size,
+            offset: (self.position.0 + label_width, self.position.1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextInputData;
+    use crossterm::event::KeyEvent;
+
+    // A field that has to be filled in
+    struct Required;
+    impl CustomInputHandler for Required {
+        fn validate(&mut self, text_data: &TextInputData) -> std::result::Result<(), String> {
+            if text_data.text.is_empty() {
+                return Err("required".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    fn field(text: &str) -> CoolInput<Required> {
+        let mut input = CoolInput::new(Required, 4);
+        input.text_data.load_text(text);
+        input
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn tab_moves_focus_around() {
+        let mut form = Form::new().field("a", field("")).field("b", field(""));
+        form.handle_event(key(KeyCode::Tab, KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(form.focused, 1);
+        form.handle_event(key(KeyCode::Tab, KeyModifiers::NONE))
+            .unwrap();
+        assert_eq!(form.focused, 0);
+        form.handle_event(key(KeyCode::BackTab, KeyModifiers::SHIFT))
+            .unwrap();
+        assert_eq!(form.focused, 1);
+    }
+
+    #[test]
+    fn submit_validates_every_field() {
+        let submit = key(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        let mut form = Form::new()
+            .field("name", field("Ada"))
+            .field("email", field(""));
+        assert_eq!(form.handle_event(submit.clone()).unwrap(), None);
+        // The first invalid field is focused
+        assert_eq!(form.focused, 1);
+        assert!(form.get_field("nothing").is_none());
+
+        let mut form = Form::new()
+            .field("name", field("Ada"))
+            .field("email", field("ada@example.com"));
+        let texts = HashMap::from([
+            ("name".to_string(), "Ada".to_string()),
+            ("email".to_string(), "ada@example.com".to_string()),
+        ]);
+        assert_eq!(
+            form.handle_event(submit).unwrap(),
+            Some(FormOutcome::Submitted(texts))
+        );
+    }
+
+    #[test]
+    fn empty_form_is_cancelled() {
+        let mut form = Form::new();
+        assert_eq!(
+            form.handle_event(key(KeyCode::Enter, KeyModifiers::NONE))
+                .unwrap(),
+            Some(FormOutcome::Cancelled)
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use crate::select::ListState;
+use crate::{
+    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputConstraints,
+    InputOutcome, InputTransform, KeyPressResult, TextInputData,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::{
+    Attribute, Color, ContentStyle, PrintStyledContent, ResetColor, SetForegroundColor,
+};
+use std::cmp;
+use std::io::{stdout, Result};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+/// How many candidates are taken from a [CandidateSource] per tick
+const CANDIDATES_PER_TICK: usize = 10_000;
+
+/// Result of [fuzzy_match]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// How well the query matched. Higher is better.
+    pub score: i64,
+    /// Character indices in the candidate that matched the query
+    pub positions: Vec<usize>,
+}
+
+// Whether a character starts a new word, following `previous`
+fn is_word_start(previous: Option<char>, c: char) -> bool {
+    match previous {
+        None => true,
+        Some(previous) => {
+            !previous.is_alphanumeric() || (previous.is_lowercase() && c.is_uppercase())
+        }
+    }
+}
+
+/// Match a query against a candidate. All characters of the query have to appear in the candidate, in order, but not necessarily next to each other.
+///
+/// Matching is case insensitive unless the query contains an uppercase character.
+/// Matches at the start of words and runs of consecutive characters score higher.
+pub fn fuzzy_match(query: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = query.chars().any(|c| c.is_uppercase());
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut index = 0;
+
+    for query_char in query.chars() {
+        let matches = |c: char| {
+            if case_sensitive {
+                c == query_char
+            } else {
+                c.to_lowercase().eq(query_char.to_lowercase())
+            }
+        };
+        let position = (index..chars.len()).find(|&i| matches(chars[i]))?;
+
+        score += 16;
+        if is_word_start(position.checked_sub(1).map(|p| chars[p]), chars[position]) {
+            score += 8;
+        }
+        match positions.last() {
+            Some(last) if position == last + 1 => score += 12,
+            Some(last) => score -= cmp::min((position - last - 1) as i64, 5),
+            None => score -= cmp::min(position as i64, 5),
+        }
+        positions.push(position);
+        index = position + 1;
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Where the candidates of a [FuzzyFinder] come from. Candidates are taken while the user types.
+pub enum CandidateSource {
+    Iterator(Box<dyn Iterator<Item = String>>),
+    Channel(Receiver<String>),
+}
+
+impl CandidateSource {
+    /// Take up to `amt` candidates. Returns false once the source is exhausted.
+    fn take(&mut self, candidates: &mut Vec<String>, amt: usize) -> bool {
+        for _ in 0..amt {
+            match self {
+                CandidateSource::Iterator(iterator) => match iterator.next() {
+                    Some(candidate) => candidates.push(candidate),
+                    None => return false,
+                },
+                CandidateSource::Channel(receiver) => match receiver.try_recv() {
+                    Ok(candidate) => candidates.push(candidate),
+                    Err(TryRecvError::Empty) => return true,
+                    Err(TryRecvError::Disconnected) => return false,
+                },
+            }
+        }
+        true
+    }
+}
+
+/// A prompt that filters a list of candidates by fuzzy matching a single line query, like fzf.
+///
+/// The query is edited like any other [input](CoolInput). Up and Down move through the ranked matches, and Enter picks one.
+pub struct FuzzyFinder {
+    pub prompt: String,
+    pub candidates: Vec<String>,
+    /// Maximum amount of rows the list of matches takes up
+    pub max_height: Option<u16>,
+    source: Option<CandidateSource>,
+    /// Indices of matching candidates and their matches, best first
+    matches: Vec<(usize, FuzzyMatch)>,
+    /// The query and amount of candidates the matches are for
+    matched: Option<(String, usize)>,
+    state: ListState,
+}
+
+impl FuzzyFinder {
+    pub fn new(prompt: &str, candidates: Vec<String>) -> Self {
+        FuzzyFinder {
+            prompt: prompt.to_string(),
+            candidates,
+            max_height: None,
+            source: None,
+            matches: Vec::new(),
+            matched: None,
+            state: ListState::default(),
+        }
+    }
+    /// Create a finder with candidates that are streamed in from a [source](CandidateSource)
+    pub fn with_source(prompt: &str, source: CandidateSource) -> Self {
+        let mut finder = FuzzyFinder::new(prompt, Vec::new());
+        finder.source = Some(source);
+        finder
+    }
+    /// Set the maximum amount of rows the list of matches takes up
+    pub fn max_height(mut self, max_height: u16) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+    /// Prepare the terminal and show the prompt. Returns the picked candidate, or `None` if the prompt was cancelled.
+    pub fn ask(self) -> Result<Option<String>> {
+        let mut input = CoolInput::new(self, 0);
+        input.status_on_last_row = true;
+        input.text_data.constraints = InputConstraints::single_line();
+        input.tick_rate = Some(Duration::from_millis(50));
+        match input.listen()? {
+            InputOutcome::Submitted(_) => {
+                let finder = &mut input.custom_input;
+                let index = finder.matches[finder.state.cursor].0;
+                Ok(Some(finder.candidates.swap_remove(index)))
+            }
+            InputOutcome::Cancelled => Ok(None),
+        }
+    }
+    /// Rank the candidates against the query, if it or the candidates changed
+    fn update_matches(&mut self, query: &str) {
+        if let Some((matched_query, amt_candidates)) = &self.matched {
+            if matched_query == query && *amt_candidates == self.candidates.len() {
+                return;
+            }
+            if matched_query != query {
+                self.state = ListState::default();
+            }
+        }
+        self.matches = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, candidate)| fuzzy_match(query, candidate).map(|m| (i, m)))
+            .collect();
+        let candidates = &self.candidates;
+        self.matches.sort_by(|(a_index, a), (b_index, b)| {
+            b.score
+                .cmp(&a.score)
+                .then(candidates[*a_index].len().cmp(&candidates[*b_index].len()))
+                .then(a_index.cmp(b_index))
+        });
+        self.state.cursor = cmp::min(self.state.cursor, self.matches.len().saturating_sub(1));
+        self.matched = Some((query.to_string(), self.candidates.len()));
+    }
+    fn list_transform(&self, terminal_size: &(u16, u16)) -> InputTransform {
+        let mut height = terminal_size.1.saturating_sub(1);
+        if let Some(max_height) = self.max_height {
+            height = cmp::min(height, max_height);
+        }
+        InputTransform {
+            size: (terminal_size.0, height),
+            offset: (0, 1),
+        }
+    }
+    fn draw_matches(&self, transform: &InputTransform) {
+        let width = transform.size.0 as usize;
+        for i in 0..transform.size.1 as usize {
+            let y = transform.offset.1 as usize + i;
+            let index = self.state.scroll + i;
+            let _ = set_terminal_line("", 0, y, true);
+            let Some((candidate_index, fuzzy_match)) = self.matches.get(index) else {
+                continue;
+            };
+            let selected = index == self.state.cursor;
+            let base = ContentStyle {
+                foreground_color: selected.then_some(Color::Cyan),
+                ..ContentStyle::new()
+            };
+            let mut matched = ContentStyle {
+                foreground_color: Some(Color::Yellow),
+                ..ContentStyle::new()
+            };
+            matched.attributes.set(Attribute::Bold);
+
+            let marker = if selected { "> " } else { "  " };
+            let _ = queue!(stdout(), PrintStyledContent(base.apply(marker)));
+            let candidate = &self.candidates[*candidate_index];
+            for (i, c) in candidate.chars().take(width.saturating_sub(2)).enumerate() {
+                let style = if fuzzy_match.positions.contains(&i) {
+                    matched
+                } else {
+                    base
+                };
+                let _ = queue!(stdout(), PrintStyledContent(style.apply(c)));
+            }
+        }
+    }
+}
+
+impl CustomInputHandler for FuzzyFinder {
+    fn handle_key_press(&mut self, key: &Event, ctx: HandlerContext) -> KeyPressResult {
+        let height = self.list_transform(ctx.terminal_size).size.1 as usize;
+        if let Event::Key(key_event) = key {
+            if key_event.kind == KeyEventKind::Press {
+                let last = self.matches.len().saturating_sub(1);
+                let cursor = self.state.cursor;
+                self.state.cursor = match key_event.code {
+                    KeyCode::Enter => return KeyPressResult::Submit,
+                    KeyCode::Esc => return KeyPressResult::Stop,
+                    KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return KeyPressResult::Stop;
+                    }
+                    KeyCode::Up => cursor.saturating_sub(1),
+                    KeyCode::Down => cmp::min(cursor + 1, last),
+                    KeyCode::PageUp => cursor.saturating_sub(height),
+                    KeyCode::PageDown => cmp::min(cursor + height, last),
+                    _ => return KeyPressResult::Continue,
+                };
+                self.state.scroll_to_cursor(height);
+                return KeyPressResult::Handled;
+            }
+        }
+        KeyPressResult::Continue
+    }
+    fn after_draw_text(&mut self, ctx: HandlerContext) {
+        self.update_matches(&ctx.text_data.text);
+        let _ = queue!(stdout(), ResetColor);
+        let _ = set_terminal_line(&self.prompt, 0, 0, false);
+
+        let counter = format!("{}/{}", self.matches.len(), self.candidates.len());
+        let counter_x = (ctx.terminal_size.0 as usize).saturating_sub(counter.len());
+        let _ = queue!(stdout(), SetForegroundColor(Color::DarkGrey));
+        let _ = set_terminal_line(&counter, counter_x, 0, false);
+        let _ = queue!(stdout(), ResetColor);
+
+        let transform = self.list_transform(ctx.terminal_size);
+        self.draw_matches(&transform);
+    }
+    fn on_tick(&mut self, _: HandlerContext) -> bool {
+        let Some(source) = &mut self.source else {
+            return false;
+        };
+        let amt_candidates = self.candidates.len();
+        if !source.take(&mut self.candidates, CANDIDATES_PER_TICK) {
+            self.source = None;
+        }
+        self.candidates.len() != amt_candidates
+    }
+    fn validate(&mut self, text_data: &TextInputData) -> std::result::Result<(), String> {
+        self.update_matches(&text_data.text);
+        if self.matches.is_empty() {
+            return Err("no matches".to_string());
+        }
+        Ok(())
+    }
+    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
+        let prompt_offset = self.prompt.chars().count() as u16 + 1;
+        // Leave room for the match counter
+        let width = ctx.terminal_size.0.saturating_sub(prompt_offset + 12);
+        InputTransform {
+            size: (width, 1),
+            offset: (prompt_offset, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_in_order() {
+        assert_eq!(fuzzy_match("fb", "foo bar").unwrap().positions, vec![0, 4]);
+        assert!(fuzzy_match("bf", "foo bar").is_none());
+        assert!(fuzzy_match("x", "foo bar").is_none());
+    }
+
+    #[test]
+    fn smart_case() {
+        assert!(fuzzy_match("foo", "FooBar").is_some());
+        assert!(fuzzy_match("Foo", "foobar").is_none());
+        assert!(fuzzy_match("FB", "FooBar").is_some());
+    }
+
+    #[test]
+    fn word_starts_and_runs_score_higher() {
+        let score = |query, candidate| fuzzy_match(query, candidate).unwrap().score;
+        assert!(score("fb", "foo_bar") > score("fb", "fabric"));
+        assert!(score("fb", "FooBar") > score("fb", "fooxbar"));
+        assert!(score("bar", "bar") > score("bar", "b_a_r"));
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(
+            fuzzy_match("", "anything"),
+            Some(FuzzyMatch {
+                score: 0,
+                positions: Vec::new()
+            })
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::highlight::Cell;
+use crossterm::style::{Attribute, Color, ContentStyle};
+use std::cmp;
+
+/// How line numbers are shown in the [gutter](Gutter)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineNumbers {
+    /// Don't show line numbers
+    Off,
+    /// Show the number of each line, starting at 1
+    Absolute,
+    /// Show the distance of each line to the cursor. The cursor's line shows its absolute number.
+    Relative,
+}
+
+/// A marker shown next to a line in the sign column of the [gutter](Gutter)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sign {
+    pub symbol: char,
+    pub style: ContentStyle,
+}
+
+impl Sign {
+    pub fn new(symbol: char, color: Color) -> Self {
+        Sign {
+            symbol,
+            style: ContentStyle {
+                foreground_color: Some(color),
+                ..ContentStyle::new()
+            },
+        }
+    }
+    pub fn error() -> Self {
+        Sign::new('E', Color::Red)
+    }
+    pub fn warning() -> Self {
+        Sign::new('W', Color::Yellow)
+    }
+    pub fn changed() -> Self {
+        Sign::new('~', Color::Blue)
+    }
+}
+
+/// Settings for the gutter drawn to the left of the text of an [input](crate::CoolInput).
+///
+/// The gutter takes its width from the [input transform](crate::InputTransform), so the text area shrinks to make room for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gutter {
+    pub line_numbers: LineNumbers,
+    /// Show a column where the [input handler](crate::CustomInputHandler::get_sign) can place a [Sign] for each line
+    pub sign_column: bool,
+}
+
+impl Default for Gutter {
+    fn default() -> Self {
+        Gutter {
+            line_numbers: LineNumbers::Off,
+            sign_column: false,
+        }
+    }
+}
+
+impl Gutter {
+    fn number_width(&self, amt_lines: usize) -> usize {
+        match self.line_numbers {
+            LineNumbers::Off => 0,
+            _ => cmp::max(amt_lines.to_string().len(), 2),
+        }
+    }
+    /// Get the amount of columns the gutter takes up for a document with `amt_lines` lines
+    pub fn width(&self, amt_lines: usize) -> u16 {
+        let mut width = self.number_width(amt_lines);
+        if self.sign_column {
+            width += 1;
+        }
+        if width > 0 {
+            // Space between the gutter and the text
+            width += 1;
+        }
+        width as u16
+    }
+    /// Get the cells of the gutter for a line. `line` is `None` for rows after the end of the document.
+    pub(crate) fn cells(
+        &self,
+        line: Option<usize>,
+        cursor_y: usize,
+        amt_lines: usize,
+        sign: Option<Sign>,
+    ) -> Vec<Cell> {
+        let mut cells = Vec::new();
+        let plain = ContentStyle::new();
+        if self.sign_column {
+            match sign {
+                Some(sign) => cells.push(Cell {
+                    c: sign.symbol,
+                    style: sign.style,
+                }),
+                None => cells.push(Cell {
+                    c: ' ',
+                    style: plain,
+                }),
+            }
+        }
+        let number_width = self.number_width(amt_lines);
+        if number_width > 0 {
+            let number = match (line, self.line_numbers) {
+                (Some(y), LineNumbers::Relative) if y != cursor_y => {
+                    y.abs_diff(cursor_y).to_string()
+                }
+                (Some(y), _) => (y + 1).to_string(),
+                (None, _) => String::new(),
+            };
+            let mut style = ContentStyle {
+                foreground_color: Some(Color::DarkGrey),
+                ..ContentStyle::new()
+            };
+            if line == Some(cursor_y) {
+                style.foreground_color = None;
+                style.attributes.set(Attribute::Bold);
+            }
+            for c in format!("{number:>number_width$}").chars() {
+                cells.push(Cell { c, style });
+            }
+        }
+        if !cells.is_empty() {
+            cells.push(Cell {
+                c: ' ',
+                style: plain,
+            });
+        }
+        cells
+    }
+}
@@ -0,0 +1,154 @@
+use crossterm::style::ContentStyle;
+
+/// A styled range of characters on a single line of an [input](crate::CoolInput).
+///
+/// Returned by [CustomInputHandler's get_highlights](crate::CustomInputHandler::get_highlights).
+/// When several highlights cover the same character, the later ones are applied on top of the earlier ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Highlight {
+    /// Index of the line the highlight is on
+    pub line: usize,
+    /// Character index where the highlight starts (inclusive)
+    pub start: usize,
+    /// Character index where the highlight ends (exclusive)
+    pub end: usize,
+    pub style: ContentStyle,
+    /// Hide the highlighted characters when rendering. The text itself is left untouched.
+    pub conceal: bool,
+}
+
+impl Highlight {
+    pub fn new(line: usize, start: usize, end: usize, style: ContentStyle) -> Self {
+        Highlight {
+            line,
+            start,
+            end,
+            style,
+            conceal: false,
+        }
+    }
+    /// Create a highlight that hides its characters when rendered
+    pub fn concealed(line: usize, start: usize, end: usize) -> Self {
+        Highlight {
+            line,
+            start,
+            end,
+            style: ContentStyle::new(),
+            conceal: true,
+        }
+    }
+}
+
+/// How the text of a masked [input](crate::TextInputData::mask) is drawn, ex. for passwords
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mask {
+    /// Draw this character in place of every character of the text
+    Char(char),
+    /// Don't draw the text at all
+    Hidden,
+}
+
+/// A single character as it is drawn on screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Cell {
+    pub c: char,
+    pub style: ContentStyle,
+}
+
+/// A line of text converted to the cells that are drawn on screen
+pub(crate) struct LineLayout {
+    pub cells: Vec<Cell>,
+    /// The screen column of each character of the line, with one extra entry for the end of the line
+    pub columns: Vec<usize>,
+}
+
+// Apply the set values of one style on top of another
+pub(crate) fn merge_style(base: &mut ContentStyle, other: &ContentStyle) {
+    if other.foreground_color.is_some() {
+        base.foreground_color = other.foreground_color;
+    }
+    if other.background_color.is_some() {
+        base.background_color = other.background_color;
+    }
+    if other.underline_color.is_some() {
+        base.underline_color = other.underline_color;
+    }
+    base.attributes = base.attributes | other.attributes;
+}
+
+/// Lay out a line of text, applying the highlights that are on it. Highlights are ignored if the text is masked.
+///
+/// Tabs are expanded with spaces to the next multiple of `tab_width`.
+pub(crate) fn layout_line(
+    line: &str,
+    highlights: &[&Highlight],
+    mask: Option<Mask>,
+    tab_width: usize,
+) -> LineLayout {
+    let mut cells = Vec::new();
+    let mut columns = Vec::new();
+    for (i, c) in line.chars().enumerate() {
+        columns.push(cells.len());
+        match mask {
+            Some(Mask::Char(mask_char)) => {
+                cells.push(Cell {
+                    c: mask_char,
+                    style: ContentStyle::new(),
+                });
+                continue;
+            }
+            Some(Mask::Hidden) => continue,
+            None => {}
+        }
+        let mut style = ContentStyle::new();
+        let mut concealed = false;
+        for highlight in highlights {
+            if highlight.start <= i && i < highlight.end {
+                merge_style(&mut style, &highlight.style);
+                concealed |= highlight.conceal;
+            }
+        }
+        if concealed {
+            continue;
+        }
+        if c == '\t' {
+            let tab_width = tab_width.max(1);
+            let amt = tab_width - cells.len() % tab_width;
+            cells.extend(std::iter::repeat(Cell { c: ' ', style }).take(amt));
+        } else {
+            cells.push(Cell { c, style });
+        }
+    }
+    columns.push(cells.len());
+    LineLayout { cells, columns }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(layout: &LineLayout) -> String {
+        layout.cells.iter().map(|cell| cell.c).collect()
+    }
+
+    #[test]
+    fn tabs_go_to_tab_stops() {
+        let layout = layout_line("a\tbc\td", &[], None, 4);
+        assert_eq!(text(&layout), "a   bc  d");
+        assert_eq!(layout.columns, vec![0, 1, 4, 5, 6, 8, 9]);
+    }
+
+    #[test]
+    fn concealed_and_masked() {
+        let conceal = Highlight::concealed(0, 1, 3);
+        let layout = layout_line("abcd", &[&conceal], None, 4);
+        assert_eq!(text(&layout), "ad");
+        assert_eq!(layout.columns, vec![0, 1, 1, 1, 2]);
+
+        let layout = layout_line("a\tb", &[&conceal], Some(Mask::Char('*')), 4);
+        assert_eq!(text(&layout), "***");
+        let layout = layout_line("abc", &[], Some(Mask::Hidden), 4);
+        assert_eq!(text(&layout), "");
+    }
+}
@@ -0,0 +1,182 @@
+use crate::TextInputData;
+use std::io::Result;
+
+impl TextInputData {
+    // Run an edit that may touch other lines than the cursor's, keeping the main cursor anchored to the text
+    fn keeping_cursor(&mut self, edit: impl FnOnce(&mut Self) -> Result<()>) -> Result<()> {
+        let cursor = self.get_main_cursor();
+        self.extra_cursors.push(cursor);
+        let result = edit(self);
+        if let Some(cursor) = self.extra_cursors.pop() {
+            self.set_main_cursor(cursor);
+        }
+        result
+    }
+    /// Get the text of one level of indentation: a tab with [hard tabs](TextInputData::hard_tabs), otherwise [tab_width](TextInputData::tab_width) spaces
+    pub fn get_indent_unit(&self) -> String {
+        if self.hard_tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(self.tab_width)
+        }
+    }
+    /// Get the leading whitespace of a line
+    pub fn get_indent(&self, y: usize) -> &str {
+        let line = self.get_line_at(y).unwrap_or("");
+        let end = line.len() - line.trim_start_matches([' ', '\t']).len();
+        &line[..end]
+    }
+    /// Get the lines selected by the main cursor, or the cursor's line if nothing is selected.
+    /// A selection ending at the start of a line doesn't include that line.
+    pub fn get_selected_lines(&self) -> (usize, usize) {
+        match self.get_selection() {
+            Some((start, end)) if end.0 == 0 && end.1 > start.1 => (start.1, end.1 - 1),
+            Some((start, end)) => (start.1, end.1),
+            None => (self.cursor_y, self.cursor_y),
+        }
+    }
+    /// Indent each line from `top` to `bottom` by one [level](TextInputData::get_indent_unit)
+    pub fn indent_lines(&mut self, top: usize, bottom: usize) -> Result<()> {
+        let indent = self.get_indent_unit();
+        self.keeping_cursor(|text_data| {
+            for y in top..=bottom {
+                for c in indent.chars().rev() {
+                    text_data.insert_char(c, 0, y);
+                }
+            }
+            Ok(())
+        })
+    }
+    /// Remove up to one level of indentation from each line from `top` to `bottom`
+    pub fn dedent_lines(&mut self, top: usize, bottom: usize) -> Result<()> {
+        self.keeping_cursor(|text_data| {
+            for y in top..=bottom {
+                // A level is a tab, or up to tab_width spaces
+                let indent = text_data.get_indent(y);
+                let amt = if indent.starts_with('\t') {
+                    1
+                } else {
+                    indent
+                        .chars()
+                        .take_while(|c| *c == ' ')
+                        .take(text_data.tab_width)
+                        .count()
+                };
+                for x in (1..=amt).rev() {
+                    text_data.remove_character(x, y)?;
+                }
+            }
+            Ok(())
+        })
+    }
+    /// Insert a line break at the cursor. With [auto_indent](TextInputData::auto_indent), the new line gets the indentation of the line it was split from,
+    /// plus one level if that line ends with one of the [indent_after](TextInputData::indent_after) characters.
+    pub fn write_line_break(&mut self) -> Result<()> {
+        if !self.write_char('\n')? || !self.auto_indent {
+            return Ok(());
+        }
+        let previous_y = self.cursor_y - 1;
+        let mut indent = self.get_indent(previous_y).to_string();
+        let opens_block = self
+            .get_line_at(previous_y)
+            .and_then(|line| line.trim_end().chars().next_back())
+            .is_some_and(|c| self.indent_after.contains(&c));
+        if opens_block {
+            indent += &self.get_indent_unit();
+        }
+        self.write_str(&indent)
+    }
+    // Remove the spaces before the cursor back to the previous tab stop, if the cursor is in the leading whitespace of its line.
+    // Returns whether anything was removed.
+    pub(crate) fn backspace_to_tab_stop(&mut self) -> Result<bool> {
+        let x = self.cursor_x;
+        if self.tab_width == 0 || x == 0 {
+            return Ok(false);
+        }
+        let line = self.get_line_at(self.cursor_y).unwrap_or("");
+        if !line.chars().take(x).all(|c| c == ' ') {
+            return Ok(false);
+        }
+        let amt = (x - 1) % self.tab_width + 1;
+        for _ in 0..amt {
+            if !self.remove_character(self.cursor_x, self.cursor_y)? {
+                break;
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_data(text: &str) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(text);
+        text_data
+    }
+
+    #[test]
+    fn indent_and_dedent_lines() {
+        let mut text_data = text_data("a\n  b\nc");
+        text_data.cursor_x = 1;
+        text_data.indent_lines(0, 1).unwrap();
+        assert_eq!(text_data.text, "    a\n      b\nc");
+        // The cursor stays on the same character
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (5, 0));
+        text_data.dedent_lines(0, 2).unwrap();
+        text_data.dedent_lines(0, 2).unwrap();
+        assert_eq!(text_data.text, "a\nb\nc");
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (1, 0));
+    }
+
+    #[test]
+    fn hard_tabs_indent_with_tabs() {
+        let mut text_data = text_data("a\n\t\tb");
+        text_data.hard_tabs = true;
+        text_data.indent_lines(0, 0).unwrap();
+        text_data.dedent_lines(1, 1).unwrap();
+        assert_eq!(text_data.text, "\ta\n\tb");
+    }
+
+    #[test]
+    fn selected_lines() {
+        let mut text_data = text_data("a\nb\nc");
+        text_data.selection_anchor = Some((0, 0));
+        (text_data.cursor_x, text_data.cursor_y) = (0, 2);
+        assert_eq!(text_data.get_selected_lines(), (0, 1));
+        text_data.cursor_x = 1;
+        assert_eq!(text_data.get_selected_lines(), (0, 2));
+    }
+
+    #[test]
+    fn auto_indent() {
+        let mut text_data = text_data("    if x {");
+        text_data.cursor_x = 10;
+        text_data.write_line_break().unwrap();
+        assert_eq!(text_data.text, "    if x {\n");
+
+        text_data.auto_indent = true;
+        text_data.indent_after = vec!['{'];
+        text_data.write_str("    y").unwrap();
+        text_data.write_line_break().unwrap();
+        text_data.write_str("z").unwrap();
+        assert_eq!(text_data.text, "    if x {\n    y\n    z");
+        (text_data.cursor_x, text_data.cursor_y) = (10, 0);
+        text_data.write_line_break().unwrap();
+        assert_eq!(text_data.get_indent(1), "        ");
+    }
+
+    #[test]
+    fn backspace_to_tab_stop() {
+        let mut text_data = text_data("      x");
+        text_data.cursor_x = 6;
+        assert!(text_data.backspace_to_tab_stop().unwrap());
+        assert_eq!(text_data.text, "    x");
+        assert!(text_data.backspace_to_tab_stop().unwrap());
+        assert_eq!(text_data.text, "x");
+        text_data.cursor_x = 1;
+        assert!(!text_data.backspace_to_tab_stop().unwrap());
+    }
+}
@@ -0,0 +1,279 @@
+use crate::anchor;
+use crate::{CoolInput, CustomInputHandler, TextInputData};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::cmp;
+use std::io::Result;
+
+/// How many positions a [JumpList] remembers
+const MAX_JUMPS: usize = 100;
+
+/// Positions the cursor jumped away from, to go back and forward between them like in a browser.
+///
+/// Large cursor moves are recorded: search hits, go to line, marks and the start or end of the document.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JumpList {
+    back: Vec<(usize, usize)>,
+    forward: Vec<(usize, usize)>,
+}
+
+impl JumpList {
+    /// Record a position the cursor jumped away from. This clears the positions to go forward to.
+    pub fn push(&mut self, position: (usize, usize)) {
+        self.forward.clear();
+        if self.back.last() != Some(&position) {
+            self.back.push(position);
+        }
+        if self.back.len() > MAX_JUMPS {
+            self.back.remove(0);
+        }
+    }
+    /// Take the previous position, remembering `current` to go forward to
+    pub fn back(&mut self, current: (usize, usize)) -> Option<(usize, usize)> {
+        let position = self.back.pop()?;
+        self.forward.push(current);
+        Some(position)
+    }
+    /// Take the next position, remembering `current` to go back to
+    pub fn forward(&mut self, current: (usize, usize)) -> Option<(usize, usize)> {
+        let position = self.forward.pop()?;
+        self.back.push(current);
+        Some(position)
+    }
+    pub(crate) fn positions_mut(&mut self) -> impl Iterator<Item = &mut (usize, usize)> {
+        self.back.iter_mut().chain(self.forward.iter_mut())
+    }
+}
+
+/// A prompt shown in the status row of a [CoolInput], waiting for where to jump to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JumpPrompt {
+    /// Go to a line typed as `line` or `line:column`, opened with CTRL + G
+    GoToLine(String),
+    /// Set a mark named by the next character typed, opened with ALT + M
+    SetMark,
+    /// Jump to the mark named by the next character typed, opened with ALT + J
+    GoToMark,
+}
+
+/// Parse a 1 based `line` or `line:column` into a position
+fn parse_line_column(text: &str) -> std::result::Result<(usize, usize), String> {
+    let (line, column) = match text.split_once(':') {
+        Some((line, column)) => (line, Some(column)),
+        None => (text, None),
+    };
+    let line: usize = line
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid line: {line}"))?;
+    let column: usize = match column {
+        Some(column) => column
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid column: {column}"))?,
+        None => 1,
+    };
+    Ok((column.saturating_sub(1), line.saturating_sub(1)))
+}
+
+impl TextInputData {
+    /// Move the main cursor to a position, recording where it was in the [jump list](TextInputData::jumps).
+    /// The position is moved inside the text if it is past the end of its line or of the text.
+    pub fn jump_to(&mut self, position: (usize, usize)) {
+        self.jumps.push((self.cursor_x, self.cursor_y));
+        self.move_cursor_to(position);
+    }
+    // Move the main cursor to a position inside the text, dropping other cursors and selections
+    fn move_cursor_to(&mut self, position: (usize, usize)) {
+        self.extra_cursors.clear();
+        self.selection_anchor = None;
+        self.block_selection = None;
+        self.cursor_y = cmp::min(position.1, self.get_amt_lines().saturating_sub(1));
+        self.cursor_x = cmp::min(position.0, self.line_length(self.cursor_y));
+    }
+    /// Go to a 1 based `line` or `line:column`, recording the jump. Returns an error message if the text isn't a line.
+    pub fn go_to_line(&mut self, line: &str) -> std::result::Result<(), String> {
+        let position = parse_line_column(line)?;
+        self.jump_to(position);
+        Ok(())
+    }
+    /// Go back to the previous position in the [jump list](TextInputData::jumps). Returns whether there was one.
+    pub fn jump_back(&mut self) -> bool {
+        match self.jumps.back((self.cursor_x, self.cursor_y)) {
+            Some(position) => {
+                self.move_cursor_to(position);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Go forward to the next position in the [jump list](TextInputData::jumps). Returns whether there was one.
+    pub fn jump_forward(&mut self) -> bool {
+        match self.jumps.forward((self.cursor_x, self.cursor_y)) {
+            Some(position) => {
+                self.move_cursor_to(position);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Name the cursor's position. Marks move along with the text as it is edited.
+    pub fn set_mark(&mut self, name: char) {
+        self.marks.insert(name, (self.cursor_x, self.cursor_y));
+    }
+    /// Jump to a mark. Returns whether the mark exists.
+    pub fn go_to_mark(&mut self, name: char) -> bool {
+        match self.marks.get(&name).copied() {
+            Some(position) => {
+                self.jump_to(position);
+                true
+            }
+            None => false,
+        }
+    }
+    // Keep the jump list and marks anchored after `c` was inserted at (x, y)
+    pub(crate) fn shift_jumps_on_insert(&mut self, c: char, x: usize, y: usize) {
+        for position in self.jumps.positions_mut().chain(self.marks.values_mut()) {
+            anchor::shift_on_insert(position, c, x, y, false);
+        }
+    }
+    // Keep the jump list and marks anchored after the character before (x, y) was removed
+    pub(crate) fn shift_jumps_on_remove(
+        &mut self,
+        x: usize,
+        y: usize,
+        previous_line_length: usize,
+    ) {
+        for position in self.jumps.positions_mut().chain(self.marks.values_mut()) {
+            anchor::shift_on_remove(position, x, y, previous_line_length);
+        }
+    }
+}
+
+impl<H: CustomInputHandler> CoolInput<H> {
+    // Handle a key press while a jump prompt is open
+    pub(crate) fn handle_jump_prompt_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
+        let Some(prompt) = &mut self.jump_prompt else {
+            return Ok(());
+        };
+        let old_cursor = (self.text_data.cursor_x, self.text_data.cursor_y);
+        match (prompt, key_event.code) {
+            (_, KeyCode::Esc) => {}
+            (JumpPrompt::GoToLine(line), KeyCode::Char(c)) => {
+                line.push(c);
+                return Ok(());
+            }
+            (JumpPrompt::GoToLine(line), KeyCode::Backspace) => {
+                line.pop();
+                return Ok(());
+            }
+            (JumpPrompt::GoToLine(line), KeyCode::Enter) if self.viewer.is_some() => {
+                let position = parse_line_column(line);
+                self.jump_prompt = None;
+                return match position {
+                    Ok(position) => self.scroll_viewer_to(position),
+                    Err(error) => {
+                        self.validation_error = Some(error);
+                        Ok(())
+                    }
+                };
+            }
+            (JumpPrompt::GoToLine(line), KeyCode::Enter) => {
+                if let Err(error) = self.text_data.go_to_line(line) {
+                    self.validation_error = Some(error);
+                }
+            }
+            (JumpPrompt::SetMark, KeyCode::Char(c)) => self.text_data.set_mark(c),
+            (JumpPrompt::GoToMark, KeyCode::Char(c)) => {
+                if !self.text_data.go_to_mark(c) {
+                    self.validation_error = Some(format!("no mark {c}"));
+                }
+            }
+            _ => return Ok(()),
+        }
+        self.jump_prompt = None;
+        self.scroll_to_jump(old_cursor)
+    }
+    // Scroll the cursor into view after it jumped from `old_cursor`
+    fn scroll_to_jump(&mut self, old_cursor: (usize, usize)) -> Result<()> {
+        self.scroll_in_view(
+            self.text_data.cursor_x > old_cursor.0,
+            self.text_data.cursor_y > old_cursor.1,
+        )
+    }
+    // Get the text of the open jump prompt
+    pub(crate) fn get_jump_prompt_text(&self) -> Option<String> {
+        Some(match self.jump_prompt.as_ref()? {
+            JumpPrompt::GoToLine(line) => format!("Go to line: {line}"),
+            JumpPrompt::SetMark => "Set mark: ".to_string(),
+            JumpPrompt::GoToMark => "Go to mark: ".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_data(text: &str) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(text);
+        text_data
+    }
+
+    #[test]
+    fn parse_line_and_column() {
+        assert_eq!(parse_line_column("3"), Ok((0, 2)));
+        assert_eq!(parse_line_column(" 3 : 5 "), Ok((4, 2)));
+        assert!(parse_line_column("x").is_err());
+        assert!(parse_line_column("3:x").is_err());
+    }
+
+    #[test]
+    fn go_to_line_stays_inside_the_text() {
+        let mut text_data = text_data("one\ntwo\nthree");
+        text_data.go_to_line("2:3").unwrap();
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (2, 1));
+        text_data.go_to_line("10:10").unwrap();
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (5, 2));
+    }
+
+    #[test]
+    fn jump_back_and_forward() {
+        let mut text_data = text_data("one\ntwo\nthree");
+        text_data.jump_to((1, 1));
+        text_data.jump_to((2, 2));
+        assert!(text_data.jump_back());
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (1, 1));
+        assert!(text_data.jump_back());
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (0, 0));
+        assert!(!text_data.jump_back());
+        assert!(text_data.jump_forward());
+        assert!(text_data.jump_forward());
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (2, 2));
+        assert!(!text_data.jump_forward());
+    }
+
+    #[test]
+    fn jump_list_is_limited() {
+        let mut jumps = JumpList::default();
+        for y in 0..MAX_JUMPS + 10 {
+            jumps.push((0, y));
+        }
+        jumps.push((0, MAX_JUMPS + 9));
+        assert_eq!(jumps.back.len(), MAX_JUMPS);
+        assert_eq!(jumps.back[0], (0, 10));
+    }
+
+    #[test]
+    fn marks_move_with_the_text() {
+        let mut text_data = text_data("abc\ndef");
+        (text_data.cursor_x, text_data.cursor_y) = (1, 1);
+        text_data.set_mark('a');
+        (text_data.cursor_x, text_data.cursor_y) = (0, 0);
+        text_data.write_str("x\n").unwrap();
+        assert!(text_data.go_to_mark('a'));
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (1, 2));
+        assert!(!text_data.go_to_mark('b'));
+    }
+}
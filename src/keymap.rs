@@ -0,0 +1,96 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+/// Keys that are translated to other keys before an [input](crate::CoolInput) handles them,
+/// ex. to use CTRL + H/J/K/L as arrow keys, or CTRL + Q to quit like Escape.
+///
+/// The handler gets the translated key, so bindings work the same for built-in keys and the handler's own.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: HashMap<(KeyCode, KeyModifiers), (KeyCode, KeyModifiers)>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Keymap::default()
+    }
+    /// Make pressing `from` with `from_modifiers` act like pressing `to` with `to_modifiers`
+    pub fn bind(
+        mut self,
+        from: KeyCode,
+        from_modifiers: KeyModifiers,
+        to: KeyCode,
+        to_modifiers: KeyModifiers,
+    ) -> Self {
+        self.bindings
+            .insert((from, from_modifiers), (to, to_modifiers));
+        self
+    }
+    /// Remove the binding of a key, so it is handled as itself again
+    pub fn unbind(mut self, key: KeyCode, modifiers: KeyModifiers) -> Self {
+        self.bindings.remove(&(key, modifiers));
+        self
+    }
+    /// Get the key that a key press acts like
+    pub fn translate(&self, key_event: KeyEvent) -> KeyEvent {
+        match self.bindings.get(&(key_event.code, key_event.modifiers)) {
+            Some((code, modifiers)) => KeyEvent {
+                code: *code,
+                modifiers: *modifiers,
+                ..key_event
+            },
+            None => key_event,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEventKind;
+
+    #[test]
+    fn translate_bound_keys() {
+        let keymap = Keymap::new()
+            .bind(
+                KeyCode::Char('h'),
+                KeyModifiers::CONTROL,
+                KeyCode::Left,
+                KeyModifiers::NONE,
+            )
+            .bind(
+                KeyCode::Char('q'),
+                KeyModifiers::CONTROL,
+                KeyCode::Esc,
+                KeyModifiers::NONE,
+            );
+        let key_event = KeyEvent::new_with_kind(
+            KeyCode::Char('h'),
+            KeyModifiers::CONTROL,
+            KeyEventKind::Release,
+        );
+        let translated = keymap.translate(key_event);
+        assert_eq!(
+            (translated.code, translated.modifiers),
+            (KeyCode::Left, KeyModifiers::NONE)
+        );
+        assert_eq!(translated.kind, KeyEventKind::Release);
+
+        // Only the exact modifiers are bound
+        let key_event = KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE);
+        assert_eq!(keymap.translate(key_event), key_event);
+    }
+
+    #[test]
+    fn unbind() {
+        let keymap = Keymap::new()
+            .bind(
+                KeyCode::Char('q'),
+                KeyModifiers::CONTROL,
+                KeyCode::Esc,
+                KeyModifiers::NONE,
+            )
+            .unbind(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(keymap, Keymap::new());
+    }
+}
@@ -1,32 +1,75 @@
 use crossterm::event::{
-    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
-    KeyModifiers, MouseEventKind,
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
 };
 use crossterm::{
     cursor, execute, queue,
-    style::ResetColor,
+    style::{Color, ContentStyle, PrintStyledContent, ResetColor},
     terminal::{self, disable_raw_mode, enable_raw_mode},
 };
 use std::cmp;
+use std::collections::HashMap;
 use std::io::Result;
 use std::io::{self, stdout, Write};
+use std::time::Duration;
 
-// Get slice of string by starting character index and end character index
-fn get_slice_of_string(text: &str, start: usize, end: usize) -> String {
-    let mut new_text = String::new();
-    let length = text.chars().count();
+mod anchor;
+mod annotation;
+mod block;
+mod builder;
+mod constraints;
+mod cursors;
+mod diff;
+mod document;
+mod edit;
+mod form;
+mod fuzzy;
+mod gutter;
+mod highlight;
+mod indent;
+mod jumps;
+mod keymap;
+mod line_ending;
+mod markdown;
+mod protect;
+mod search;
+mod select;
+mod state;
+mod swap;
+mod typed;
+mod undo;
+mod viewer;
 
-    for i in start..end {
-        if i >= length {
-            break;
-        }
-        new_text.insert(
-            new_text.len(),
-            text.chars().nth(i).expect("Char at pos should exist"),
-        );
-    }
-    new_text
-}
+pub use annotation::{Annotation, Severity};
+pub use block::{BlockSelection, Clipboard};
+pub use builder::{BuildError, CoolInputBuilder};
+pub use constraints::InputConstraints;
+use constraints::TextCounts;
+pub use cursors::Cursor;
+pub use diff::{diff_lines, DiffLine};
+pub use document::{DiskChange, Document, DocumentError, Encoding};
+pub use edit::Edit;
+use edit::PendingEdit;
+pub use form::{Form, FormInput, FormOutcome, LabeledInput};
+pub use fuzzy::{fuzzy_match, CandidateSource, FuzzyFinder, FuzzyMatch};
+pub use gutter::{Gutter, LineNumbers, Sign};
+use highlight::{layout_line, merge_style, Cell};
+pub use highlight::{Highlight, Mask};
+pub use jumps::{JumpList, JumpPrompt};
+pub use keymap::Keymap;
+pub use line_ending::LineEnding;
+pub use markdown::{highlight_markdown, MarkdownInputHandler};
+pub use search::{Search, TextRange};
+pub use select::{Confirm, MultiSelect, Select};
+pub use state::CoolInputState;
+pub use swap::SwapFile;
+pub use typed::{
+    Date, DateInput, DateParser, InputParser, Number, NumberInput, NumberParser, TypedInput,
+};
+#[cfg(feature = "regex")]
+pub use typed::{PatternInput, PatternParser};
+pub use undo::UndoHistory;
+pub use viewer::PagedFile;
 
 /// Helper function to draw text to the screen by a coordinate
 pub fn set_terminal_line(text: &str, x: usize, y: usize, overwrite: bool) -> Result<()> {
@@ -44,6 +87,62 @@ pub fn set_terminal_line(text: &str, x: usize, y: usize, overwrite: bool) -> Res
     Ok(())
 }
 
+/// How an input takes over the terminal screen while it is listening
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ScreenMode {
+    /// Clear the screen before and after listening
+    #[default]
+    Clear,
+    /// Switch to the alternate screen while listening, so what was on screen before comes back afterwards
+    Alternate,
+}
+
+/// Prepare the terminal for input: enable raw mode, mouse capture and bracketed paste, and clear the screen.
+///
+/// Used by [CoolInput::pre_listen], and by anything that shows several inputs at once, like a [Form].
+pub fn prepare_terminal() -> Result<()> {
+    prepare_terminal_with(ScreenMode::Clear, true)
+}
+
+/// Prepare the terminal for input like [prepare_terminal], with a [ScreenMode] and optionally without capturing the mouse
+pub fn prepare_terminal_with(screen_mode: ScreenMode, mouse_capture: bool) -> Result<()> {
+    enable_raw_mode()?;
+    if screen_mode == ScreenMode::Alternate {
+        execute!(stdout(), terminal::EnterAlternateScreen)?;
+    }
+    if mouse_capture {
+        execute!(stdout(), EnableMouseCapture)?;
+    }
+    execute!(
+        stdout(),
+        EnableBracketedPaste,
+        terminal::Clear(terminal::ClearType::All),
+    )
+}
+
+/// Restore the terminal after [prepare_terminal]
+pub fn restore_terminal() -> Result<()> {
+    restore_terminal_with(ScreenMode::Clear, true)
+}
+
+/// Restore the terminal after [prepare_terminal_with], using the same [ScreenMode] and mouse capture
+pub fn restore_terminal_with(screen_mode: ScreenMode, mouse_capture: bool) -> Result<()> {
+    execute!(stdout(), ResetColor, DisableBracketedPaste)?;
+    if mouse_capture {
+        execute!(stdout(), DisableMouseCapture)?;
+    }
+    match screen_mode {
+        ScreenMode::Clear => execute!(
+            stdout(),
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0),
+        )?,
+        ScreenMode::Alternate => execute!(stdout(), terminal::LeaveAlternateScreen)?,
+    }
+    execute!(stdout(), cursor::Show)?;
+    disable_raw_mode()
+}
+
 /// A basic default input handler that implements all default functions of the [CustomInputHandler] trait.
 pub struct DefaultInputHandler;
 impl CustomInputHandler for DefaultInputHandler {}
@@ -52,12 +151,23 @@ impl CustomInputHandler for DefaultInputHandler {}
 pub enum KeyPressResult {
     /// Tells the input that this event has been handled, and shouldn't be further processed.
     Handled,
-    /// Tells the input to stop, like it is finished / submitted.
+    /// Tells the input to stop, cancelling it.
     Stop,
+    /// Tells the input to submit the text. The text is [validated](CustomInputHandler::validate) first, and the input only stops if it is valid.
+    Submit,
     /// Continue handling event as normal.
     Continue,
 }
 
+/// How an [input](CoolInput) finished, returned by [listen](CoolInput::listen)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputOutcome {
+    /// The input was submitted with valid text
+    Submitted(String),
+    /// The input was stopped without submitting
+    Cancelled,
+}
+
 /// Context given to [CustomInputHandler]
 pub struct HandlerContext<'a> {
     pub text_data: &'a mut TextInputData,
@@ -77,8 +187,14 @@ pub trait CustomInputHandler {
     fn handle_key_press(&mut self, key: &Event, ctx: HandlerContext) -> KeyPressResult {
         if let Event::Key(key_event) = key {
             if key_event.kind == KeyEventKind::Press {
-                // Make pressing Escape stop the input
+                // Make pressing Escape stop the input, or leave multiple cursor editing and block selection first
                 if let KeyCode::Esc = key_event.code {
+                    let text_data = ctx.text_data;
+                    if !text_data.extra_cursors.is_empty() || text_data.block_selection.is_some() {
+                        text_data.extra_cursors.clear();
+                        text_data.block_selection = None;
+                        return KeyPressResult::Handled;
+                    }
                     return KeyPressResult::Stop;
                 }
 
@@ -92,14 +208,43 @@ pub trait CustomInputHandler {
         }
         KeyPressResult::Continue
     }
-    /// Called before the user's text input is drawn. Here you can ex. change color of the inputted text
+    /// Called before the user's text input is drawn. Here you can ex. change color of the inputted text.
+    ///
+    /// Also called after each [highlighted](Highlight) part of the text, to restore the style of the text.
     fn before_draw_text(&mut self, ctx: HandlerContext) {
         let _ = queue!(stdout(), ResetColor);
     }
+    /// Called by the parent [input](CoolInput) before drawing, to get the [highlights](Highlight) to style the text with.
+    fn get_highlights(&mut self, ctx: HandlerContext) -> Vec<Highlight> {
+        Vec::new()
+    }
+    /// Called by the parent [input](CoolInput) for each visible line, to get the [Sign] shown next to it in the [gutter](Gutter).
+    fn get_sign(&mut self, line: usize, ctx: HandlerContext) -> Option<Sign> {
+        None
+    }
     /// Called after the user's text is drawn. Here you can ex. draw other text like information or a title of the document.
     fn after_draw_text(&mut self, ctx: HandlerContext) {}
     /// Called after the cursor is updated/drawn. Here you can ex. disable cursor blinking or hide it all together
     fn after_update_cursor(&mut self, ctx: HandlerContext) {}
+    /// Called regularly while listening, if the parent [input's tick rate](CoolInput::tick_rate) is set.
+    /// Here you can ex. check for outside changes. Return true to render the input again.
+    fn on_tick(&mut self, ctx: HandlerContext) -> bool {
+        false
+    }
+    /// Called after an event for every [edit](Edit) it made to the text, including edits by the handler itself and by pasting.
+    /// Return true to render the input again, ex. after updating highlights.
+    fn on_change(&mut self, edit: &Edit, ctx: HandlerContext) -> bool {
+        false
+    }
+    /// Called after an event if it tried to edit text that is [read-only](TextInputData::read_only) or [protected](TextInputData::protected_ranges),
+    /// with the position of the first rejected edit. Return true to render the input again, ex. after showing a message.
+    fn on_edit_rejected(&mut self, position: (usize, usize), ctx: HandlerContext) -> bool {
+        false
+    }
+    /// Called when the input is [submitted](KeyPressResult::Submit). Returning an error message keeps the input open and shows the message.
+    fn validate(&mut self, text_data: &TextInputData) -> std::result::Result<(), String> {
+        Ok(())
+    }
     /// Called by the parent [input](CoolInput) to get the input area's size and offset (in a [InputTransform]).
     fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
         let size = *ctx.terminal_size;
@@ -109,11 +254,77 @@ pub trait CustomInputHandler {
 }
 
 /// Handles key presses, writing text, and moving the cursor
+///
+/// With the `serde` feature it can be serialized, unless a [mask](TextInputData::mask) is set. A deserialized one is used as is,
+/// so call [clamp_positions](TextInputData::clamp_positions) if its positions may not fit its text, ex. when the file was edited by hand.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextInputData {
+    /// The text, with `\n` line breaks. Use [load_text](TextInputData::load_text) to set text that may have other line endings.
     pub text: String,
+    /// Line ending of the document, used when the text is [written out](TextInputData::get_text_with_line_endings)
+    pub line_ending: LineEnding,
+    /// Whether the [loaded](TextInputData::load_text) text used both `\r\n` and `\n`. Writing it out converts every line to `line_ending`, so callers may want to warn first.
+    pub mixed_line_endings: bool,
     pub cursor_x: usize,
     pub cursor_y: usize,
+    /// Width of a tab stop. Tab inserts this many spaces, unless [hard tabs](TextInputData::hard_tabs) are used.
     pub tab_width: usize,
+    /// Insert tab characters instead of spaces when pressing Tab or indenting
+    pub hard_tabs: bool,
+    /// [Annotations](Annotation) on the text. They are moved along with the text as it is edited.
+    pub annotations: Vec<Annotation>,
+    /// Draw the text masked, ex. for password prompts. The text itself is kept as is.
+    ///
+    /// Features that would reveal the text, like copying and search, are disabled while a mask is set.
+    /// Serializing fails and [snapshots](CoolInput::get_state) leave the text out.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_mask"))]
+    pub mask: Option<Mask>,
+    /// [Constraints](InputConstraints) that every insertion into the text is checked against
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub constraints: InputConstraints,
+    /// Position where the selection of the main cursor starts. The selection goes from here to the cursor.
+    pub selection_anchor: Option<(usize, usize)>,
+    /// [Cursors](Cursor) other than the main one. Typing and movement are applied to every cursor.
+    pub extra_cursors: Vec<Cursor>,
+    /// Rectangular selection, made with ALT + Shift + arrow keys or ALT + mouse drag
+    pub block_selection: Option<BlockSelection>,
+    /// Text copied with CTRL + Insert or cut with Shift + Delete, pasted with Shift + Insert
+    pub clipboard: Option<Clipboard>,
+    /// Start new lines with the indentation of the line before. Disabled by default.
+    pub auto_indent: bool,
+    /// Characters that indent the next line one level further when a line ends with them, ex. `{`, `:` and `(` for code
+    pub indent_after: Vec<char>,
+    /// Positions the cursor jumped away from. Moved along with the text as it is edited.
+    pub jumps: JumpList,
+    /// Named positions, set with ALT + M and jumped to with ALT + J. Moved along with the text as it is edited.
+    pub marks: HashMap<char, (usize, usize)>,
+    /// Reject every edit, while moving the cursor, selecting and copying still work. The handler is told with [on_edit_rejected](CustomInputHandler::on_edit_rejected).
+    /// [Loading text](TextInputData::load_text) still replaces the text.
+    pub read_only: bool,
+    /// Ranges of the text that can't be edited, ex. a fixed header of a template. Text can be inserted at their edges, but not inside them.
+    /// Moved along with the text as it is edited.
+    pub protected_ranges: Vec<TextRange>,
+    /// Edits to undo with CTRL + Z and redo with CTRL + Y. Cleared when [text is loaded](TextInputData::load_text).
+    pub undo_history: UndoHistory,
+    revision: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    rejected_edit: Option<(usize, usize)>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pending_edits: Vec<PendingEdit>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    finished_edits: Vec<Edit>,
+}
+
+// Masked text is secret, so it must not end up in a file with the rest of the TextInputData
+#[cfg(feature = "serde")]
+fn serialize_mask<S: serde::Serializer>(
+    mask: &Option<Mask>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    if mask.is_some() {
+        return Err(serde::ser::Error::custom("masked text can't be serialized"));
+    }
+    serde::Serialize::serialize(mask, serializer)
 }
 
 /// The main input type. Uses a custom input handler (a struct which implements [CustomInputHandler])
@@ -123,40 +334,136 @@ pub struct CoolInput<H: CustomInputHandler> {
     pub scroll_y: usize,
     pub listening: bool,
     pub custom_input: H,
+    pub gutter: Gutter,
+    /// Terminal row where validation errors and the message of the [annotation](Annotation) under the cursor are shown.
+    /// If `None`, there is no status row, unless `status_on_last_row` is set.
+    pub status_row: Option<u16>,
+    /// Show the status on the last row of the terminal when `status_row` is `None`.
+    /// Validation errors and the search and jump prompts use the last row while they are shown even if this isn't set.
+    pub status_on_last_row: bool,
+    /// Error from the last failed [validation](CustomInputHandler::validate). Cleared on the next key press.
+    pub validation_error: Option<String>,
+    /// How long to wait for an event before calling [on_tick](CustomInputHandler::on_tick). If `None`, the input only wakes up on events.
+    pub tick_rate: Option<Duration>,
+    /// The open [search prompt](Search), if any. Opened with CTRL + F.
+    pub search: Option<Search>,
+    /// The open [jump prompt](JumpPrompt), if any
+    pub jump_prompt: Option<JumpPrompt>,
+    /// A [large file](PagedFile) to show read-only instead of the text. The arrow keys, Page Up/Down, Home and End scroll it,
+    /// and the search and go to line prompts work on the file without loading all of it.
+    pub viewer: Option<PagedFile>,
+    /// Text drawn dimmed while the text is empty, ex. a hint of what to type
+    pub placeholder: Option<String>,
+    /// [Keys](Keymap) that are translated to other keys before they are handled
+    pub keymap: Keymap,
+    /// How the input takes over the screen while listening
+    pub screen_mode: ScreenMode,
+    /// Capture mouse events while listening, for scrolling and ALT + mouse drag. Enabled by default.
+    /// Without it, the terminal's own text selection works.
+    pub mouse_capture: bool,
+    outcome: Option<InputOutcome>,
+    /// Whether the status was drawn on the last row without a status row, so it is cleared once it is gone
+    status_on_last_row_drawn: bool,
 }
 
 impl TextInputData {
-    pub fn write_char(&mut self, c: char) -> Result<()> {
-        self.insert_char(c, self.cursor_x, self.cursor_y);
-        self.move_cursor_right()?;
-        Ok(())
+    pub fn new(tab_width: usize) -> Self {
+        TextInputData {
+            text: String::new(),
+            line_ending: LineEnding::default(),
+            mixed_line_endings: false,
+            cursor_x: 0,
+            cursor_y: 0,
+            tab_width,
+            hard_tabs: false,
+            annotations: Vec::new(),
+            mask: None,
+            constraints: InputConstraints::default(),
+            selection_anchor: None,
+            extra_cursors: Vec::new(),
+            block_selection: None,
+            clipboard: None,
+            auto_indent: false,
+            indent_after: Vec::new(),
+            jumps: JumpList::default(),
+            marks: HashMap::new(),
+            read_only: false,
+            protected_ranges: Vec::new(),
+            undo_history: UndoHistory::default(),
+            revision: 0,
+            rejected_edit: None,
+            pending_edits: Vec::new(),
+            finished_edits: Vec::new(),
+        }
     }
-    pub fn insert_char(&mut self, c: char, x: usize, y: usize) {
-        let mut new = String::new();
-        let mut cur_x = 0;
-        let mut cur_y = 0;
-
-        if x == 0 && y == 0 {
-            self.text.insert(0, c);
-        } else {
-            for char in self.text.chars() {
-                cur_x += 1;
-                if char == '\n' {
-                    cur_y += 1;
-                    cur_x = 0;
-                }
-                new.insert(new.len(), char);
-                if cur_x == x && cur_y == y {
-                    new.insert(new.len(), c);
-                }
+    /// Get the revision of the text, which goes up with every edit and never goes down.
+    /// Setting [text](TextInputData::text) directly doesn't count as an edit, but [loading text](TextInputData::load_text) does.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+    /// Insert a character at the cursor and move the cursor past it. Returns whether the character was inserted.
+    pub fn write_char(&mut self, c: char) -> Result<bool> {
+        if self.insert_char(c, self.cursor_x, self.cursor_y) {
+            self.move_cursor_right()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+    /// Write text at the cursor, like it was pasted. Characters rejected by the [constraints](InputConstraints) are skipped.
+    pub fn write_str(&mut self, text: &str) -> Result<()> {
+        let mut counts = self.constraints.count(&self.text);
+        for c in text.chars().filter(|c| *c != '\r') {
+            if self.insert_char_counted(c, self.cursor_x, self.cursor_y, &mut counts) {
+                self.move_cursor_right()?;
             }
-            self.text = new;
         }
+        Ok(())
     }
-    pub fn remove_character(&mut self, x: usize, y: usize) -> Result<()> {
-        let mut new = String::new();
-        let mut cur_x = 0;
-        let mut cur_y = 0;
+    /// Insert a character at (x, y). Returns whether the character was inserted, which it isn't if the position doesn't exist,
+    /// the text is [read-only](TextInputData::read_only) or [protected](TextInputData::protected_ranges) there, or the [constraints](InputConstraints) reject it.
+    pub fn insert_char(&mut self, c: char, x: usize, y: usize) -> bool {
+        let mut counts = self.constraints.count(&self.text);
+        self.insert_char_counted(c, x, y, &mut counts)
+    }
+    // Insert a character like insert_char, with the text counted for the constraints beforehand
+    fn insert_char_counted(
+        &mut self,
+        c: char,
+        x: usize,
+        y: usize,
+        counts: &mut TextCounts,
+    ) -> bool {
+        let Some(index) = self.get_byte_index(x, y) else {
+            return false;
+        };
+        if !self.can_insert_at(x, y) {
+            self.reject_edit(x, y);
+            return false;
+        }
+        if !self.constraints.allows_counted(c, counts) {
+            return false;
+        }
+        counts.add(c);
+        self.text.insert(index, c);
+        self.revision += 1;
+        self.record_insert(c, x, y);
+        self.shift_anchors_on_insert(c, x, y);
+        true
+    }
+    /// Remove the character before (x, y), moving the cursor back. Returns whether a character was removed,
+    /// which it isn't at the start of the text or if the text is [read-only](TextInputData::read_only) or [protected](TextInputData::protected_ranges) there.
+    pub fn remove_character(&mut self, x: usize, y: usize) -> Result<bool> {
+        if (x, y) != (0, 0) && !self.can_remove_before(x, y) {
+            self.reject_edit(x, y);
+            return Ok(false);
+        }
+        let cursor_before = (self.cursor_x, self.cursor_y);
+        let previous_line_length = match y.checked_sub(1) {
+            Some(previous_y) => self
+                .get_line_at(previous_y)
+                .map_or(0, |l| l.chars().count()),
+            None => 0,
+        };
 
         if x == 0 {
             self.move_cursor_up()?;
@@ -165,20 +472,82 @@ impl TextInputData {
             self.move_cursor_left()?;
         }
 
-        if !self.text.is_empty() {
-            for char in self.text.chars() {
-                cur_x += 1;
-                if char == '\n' {
-                    cur_y += 1;
-                    cur_x = 0;
-                }
-                if cur_x != x || cur_y != y {
-                    new.insert(new.len(), char);
-                }
+        if x == 0 && y == 0 {
+            return Ok(false);
+        }
+        if let Some(index) = self.get_byte_index(x, y) {
+            let removed = self.text[..index]
+                .chars()
+                .next_back()
+                .expect("Char before pos should exist");
+            self.text.remove(index - removed.len_utf8());
+            self.revision += 1;
+            let before = if x > 0 {
+                (x - 1, y)
+            } else {
+                (previous_line_length, y - 1)
+            };
+            self.record_remove(removed, before, (x, y), cursor_before);
+            self.shift_anchors_on_remove(x, y, previous_line_length);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+    // Keep positions in the text anchored after `c` was inserted at (x, y)
+    fn shift_anchors_on_insert(&mut self, c: char, x: usize, y: usize) {
+        for annotation in self.annotations.iter_mut() {
+            anchor::shift_on_insert(&mut annotation.start, c, x, y, true);
+            anchor::shift_on_insert(&mut annotation.end, c, x, y, false);
+        }
+        if let Some(selection_anchor) = &mut self.selection_anchor {
+            anchor::shift_on_insert(selection_anchor, c, x, y, false);
+        }
+        for cursor in self.extra_cursors.iter_mut() {
+            cursor.shift_on_insert(c, x, y);
+        }
+        self.shift_jumps_on_insert(c, x, y);
+        self.shift_protected_ranges_on_insert(c, x, y);
+    }
+    // Keep positions in the text anchored after the character before (x, y) was removed
+    fn shift_anchors_on_remove(&mut self, x: usize, y: usize, previous_line_length: usize) {
+        for annotation in self.annotations.iter_mut() {
+            anchor::shift_on_remove(&mut annotation.start, x, y, previous_line_length);
+            anchor::shift_on_remove(&mut annotation.end, x, y, previous_line_length);
+        }
+        if let Some(selection_anchor) = &mut self.selection_anchor {
+            anchor::shift_on_remove(selection_anchor, x, y, previous_line_length);
+        }
+        for cursor in self.extra_cursors.iter_mut() {
+            cursor.shift_on_remove(x, y, previous_line_length);
+        }
+        self.shift_jumps_on_remove(x, y, previous_line_length);
+        self.shift_protected_ranges_on_remove(x, y, previous_line_length);
+    }
+    /// Get the byte index in the text of the character position (x, y)
+    pub fn get_byte_index(&self, x: usize, y: usize) -> Option<usize> {
+        if x == 0 && y == 0 {
+            return Some(0);
+        }
+        let mut cur_x = 0;
+        let mut cur_y = 0;
+        for (index, char) in self.text.char_indices() {
+            cur_x += 1;
+            if char == '\n' {
+                cur_y += 1;
+                cur_x = 0;
+            }
+            if cur_x == x && cur_y == y {
+                return Some(index + char.len_utf8());
             }
         }
-        self.text = new;
-        Ok(())
+        None
+    }
+    /// Get the most severe [annotation](Annotation) at the position (x, y)
+    pub fn get_annotation_at(&self, x: usize, y: usize) -> Option<&Annotation> {
+        self.annotations
+            .iter()
+            .filter(|annotation| annotation.contains(x, y))
+            .max_by_key(|annotation| annotation.severity)
     }
     fn move_cursor_end(&mut self) -> Result<()> {
         if self.get_amt_lines() > 0 {
@@ -186,10 +555,21 @@ impl TextInputData {
         }
         Ok(())
     }
+    // Move the cursor to another line, keeping its screen column as tabs take up several columns
+    fn move_cursor_to_line(&mut self, y: usize) {
+        let column = self.column_of(self.cursor_x, self.cursor_y);
+        let (x, short) = self.index_at_column(column, y);
+        self.cursor_y = y;
+        // Stay on a tab that covers the column, instead of going past it
+        self.cursor_x = if short == 0 && x > 0 && self.column_of(x, y) > column {
+            x - 1
+        } else {
+            x
+        };
+    }
     fn move_cursor_up(&mut self) -> Result<()> {
         if self.cursor_y > 0 {
-            self.cursor_y -= 1;
-            self.cursor_x = cmp::min(self.get_current_line_length()?, self.cursor_x);
+            self.move_cursor_to_line(self.cursor_y - 1);
         } else {
             self.cursor_x = 0;
         }
@@ -197,8 +577,7 @@ impl TextInputData {
     }
     fn move_cursor_down(&mut self) -> Result<()> {
         if self.cursor_y < self.get_amt_lines() - 1 {
-            self.cursor_y += 1;
-            self.cursor_x = cmp::min(self.get_current_line_length()?, self.cursor_x);
+            self.move_cursor_to_line(self.cursor_y + 1);
         } else {
             self.move_cursor_end()?;
         }
@@ -229,72 +608,161 @@ impl TextInputData {
 
         Ok(())
     }
-    pub fn get_amt_lines(&mut self) -> usize {
-        let mut amt = self.text.lines().count();
-        if self.text.ends_with("\n") {
-            amt += 1;
+    // Lines are split on '\n' only, so a stray '\r' is an ordinary character and positions match byte indices
+    pub fn get_amt_lines(&self) -> usize {
+        if self.text.is_empty() {
+            return 0;
         }
-        amt
+        self.text.split('\n').count()
     }
-    pub fn get_line_at(&mut self, y: usize) -> Option<&str> {
-        if self.text.ends_with("\n") && y == self.text.lines().count() {
-            return Some("");
+    pub fn get_line_at(&self, y: usize) -> Option<&str> {
+        if self.text.is_empty() {
+            return None;
         }
-        self.text.lines().nth(y)
+        self.text.split('\n').nth(y)
     }
-    pub fn get_current_line_length(&mut self) -> Result<usize> {
+    pub fn get_current_line_length(&self) -> Result<usize> {
         let line = self.get_line_at(self.cursor_y);
         match line {
             Some(text) => Ok(text.chars().count()),
-            None => Err(std::io::Error::new(
-                io::ErrorKind::Other,
+            None => Err(std::io::Error::other(
                 "Couldn't get length of current line because it doesn't exist.",
             )),
         }
     }
     fn handle_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
+        let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+        let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right if alt && shift => {
+                self.move_block_cursor(key_event.code);
+                Ok(())
+            }
+            KeyCode::Home if control => {
+                self.jump_to((0, 0));
+                Ok(())
+            }
+            KeyCode::End if control => {
+                self.jump_to((usize::MAX, usize::MAX));
+                Ok(())
+            }
+            KeyCode::Left if alt => {
+                self.jump_back();
+                Ok(())
+            }
+            KeyCode::Right if alt => {
+                self.jump_forward();
+                Ok(())
+            }
+            KeyCode::Insert if control => {
+                self.copy();
+                Ok(())
+            }
+            KeyCode::Insert if shift => self.paste(),
+            KeyCode::Delete if shift => self.cut(),
+            KeyCode::Char('z') if control => {
+                self.undo()?;
+                Ok(())
+            }
+            KeyCode::Char('y') if control => {
+                self.redo()?;
+                Ok(())
+            }
+            KeyCode::Char(_) | KeyCode::Tab | KeyCode::Backspace | KeyCode::Delete
+                if self.block_selection.is_some() =>
+            {
+                // Edit every line of the rectangle, padding short lines only when inserting
+                let pad = matches!(key_event.code, KeyCode::Char(_) | KeyCode::Tab);
+                let removed = self.block_to_cursors(pad)?;
+                // The block couldn't be removed, so leave it as it is
+                if self.block_selection.is_some() {
+                    return Ok(());
+                }
+                if removed && matches!(key_event.code, KeyCode::Backspace | KeyCode::Delete) {
+                    return Ok(());
+                }
+                self.for_each_cursor(|text_data| text_data.handle_cursor_key_press(key_event))
+            }
+            _ if self.block_selection.is_some() => {
+                self.block_selection = None;
+                self.handle_key_press(key_event)
+            }
+            KeyCode::Up if control && alt => self.add_cursor_vertically(false),
+            KeyCode::Down if control && alt => self.add_cursor_vertically(true),
+            KeyCode::Char('d') if control => self.add_next_occurrence(),
+            _ => self.for_each_cursor(|text_data| text_data.handle_cursor_key_press(key_event)),
+        }
+    }
+    // Handle a key press for the main cursor
+    fn handle_cursor_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
+        // Shift + movement selects, typing replaces the selection, and Tab or Shift + Tab indent the selected lines
+        match key_event.code {
+            KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Home
+            | KeyCode::End => {
+                if key_event.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.selection_anchor
+                        .get_or_insert((self.cursor_x, self.cursor_y));
+                } else {
+                    self.selection_anchor = None;
+                }
+            }
+            KeyCode::Tab if self.get_selection().is_some() => {
+                let (top, bottom) = self.get_selected_lines();
+                return self.indent_lines(top, bottom);
+            }
+            KeyCode::BackTab => {
+                let (top, bottom) = self.get_selected_lines();
+                return self.dedent_lines(top, bottom);
+            }
+            KeyCode::Backspace | KeyCode::Delete if self.delete_selection()? => return Ok(()),
+            KeyCode::Backspace if self.backspace_to_tab_stop()? => return Ok(()),
+            KeyCode::Char(_) | KeyCode::Enter | KeyCode::Tab
+                if !self.delete_selection_for_insert()? =>
+            {
+                return Ok(());
+            }
+            _ => {}
+        }
+
         match key_event.code {
             KeyCode::Char(c) => {
-                self.insert_char(c, self.cursor_x, self.cursor_y);
-                self.move_cursor_right()?;
+                self.write_char(c)?;
             }
             KeyCode::Enter => {
-                self.insert_char('\n', self.cursor_x, self.cursor_y);
-                self.cursor_y += 1;
-                self.cursor_x = 0;
+                self.write_line_break()?;
             }
-            KeyCode::Backspace => {
-                if self.cursor_x > 0 || self.cursor_y != 0 {
-                    self.remove_character(self.cursor_x, self.cursor_y)?;
-                }
+            KeyCode::Backspace if self.cursor_x > 0 || self.cursor_y != 0 => {
+                self.remove_character(self.cursor_x, self.cursor_y)?;
             }
             KeyCode::Tab => {
-                for _ in 0..self.tab_width {
-                    self.insert_char(' ', self.cursor_x, self.cursor_y);
-                }
-                self.cursor_x += self.tab_width;
-            }
-            KeyCode::Delete => {
-                if self.get_amt_lines() > 0 {
-                    let line_length = self.get_current_line_length()?;
-                    if self.cursor_x < line_length || self.cursor_y != self.get_amt_lines() - 1 {
-                        if self.cursor_x == line_length {
-                            self.cursor_x = 0;
-                            self.cursor_y += 1;
-                        } else {
-                            self.cursor_x += 1;
-                        }
-                        self.remove_character(self.cursor_x, self.cursor_y)?;
+                let indent = self.get_indent_unit();
+                self.write_str(&indent)?;
+            }
+            KeyCode::Delete if self.get_amt_lines() > 0 => {
+                let line_length = self.get_current_line_length()?;
+                if self.cursor_x < line_length || self.cursor_y != self.get_amt_lines() - 1 {
+                    let old_cursor = (self.cursor_x, self.cursor_y);
+                    if self.cursor_x == line_length {
+                        self.cursor_x = 0;
+                        self.cursor_y += 1;
+                    } else {
+                        self.cursor_x += 1;
+                    }
+                    if !self.remove_character(self.cursor_x, self.cursor_y)? {
+                        (self.cursor_x, self.cursor_y) = old_cursor;
                     }
                 }
             }
             KeyCode::Up => {
                 self.move_cursor_up()?;
             }
-            KeyCode::Down => {
-                if self.get_amt_lines() > 0 {
-                    self.move_cursor_down()?;
-                }
+            KeyCode::Down if self.get_amt_lines() > 0 => {
+                self.move_cursor_down()?;
             }
             KeyCode::Left => {
                 self.move_cursor_left()?;
@@ -317,25 +785,59 @@ impl TextInputData {
 impl<H: CustomInputHandler> CoolInput<H> {
     pub fn new(handler: H, tab_width: usize) -> Self {
         CoolInput {
-            text_data: TextInputData {
-                text: String::new(),
-                cursor_x: 0,
-                cursor_y: 0,
-                tab_width,
-            },
+            text_data: TextInputData::new(tab_width),
             listening: false,
             scroll_x: 0,
             scroll_y: 0,
             custom_input: handler,
+            gutter: Gutter::default(),
+            status_row: None,
+            status_on_last_row: false,
+            validation_error: None,
+            tick_rate: None,
+            search: None,
+            jump_prompt: None,
+            viewer: None,
+            placeholder: None,
+            keymap: Keymap::default(),
+            screen_mode: ScreenMode::default(),
+            mouse_capture: true,
+            outcome: None,
+            status_on_last_row_drawn: false,
         }
     }
+    /// Add an [annotation](Annotation) to the text
+    pub fn add_annotation(&mut self, annotation: Annotation) {
+        self.text_data.annotations.push(annotation);
+    }
+    /// Remove all [annotations](Annotation) from the text
+    pub fn clear_annotations(&mut self) {
+        self.text_data.annotations.clear();
+    }
+    /// Get the most severe [annotation](Annotation) under the cursor
+    pub fn get_annotation_at_cursor(&self) -> Option<&Annotation> {
+        self.text_data
+            .get_annotation_at(self.text_data.cursor_x, self.text_data.cursor_y)
+    }
     /// Get the size of the terminal running the program
     pub fn get_terminal_size(&self) -> Result<(u16, u16)> {
         let mut terminal_size = terminal::size()?;
         terminal_size.1 -= 1;
         Ok(terminal_size)
     }
+    /// Get the size and offset of the text area. This is the [handler's transform](CustomInputHandler::get_input_transform), without the [gutter](Gutter).
     pub fn get_input_transform(&mut self) -> Result<InputTransform> {
+        let mut input_transform = self.get_full_input_transform()?;
+        let gutter_width = cmp::min(
+            self.gutter.width(self.get_amt_lines()),
+            input_transform.size.0,
+        );
+        input_transform.offset.0 += gutter_width;
+        input_transform.size.0 -= gutter_width;
+        Ok(input_transform)
+    }
+    // Get the size and offset of the whole input, including the gutter
+    fn get_full_input_transform(&mut self) -> Result<InputTransform> {
         let terminal_size = self.get_terminal_size()?;
         let input_transform = self.custom_input.get_input_transform(HandlerContext {
             text_data: &mut self.text_data,
@@ -358,16 +860,39 @@ impl<H: CustomInputHandler> CoolInput<H> {
         io::stdout().flush()?;
         Ok(())
     }
+    /// Render all text without updating the cursor, ex. for an input that isn't focused
+    pub fn render_text(&mut self) -> Result<()> {
+        self.update_text()?;
+        io::stdout().flush()?;
+        Ok(())
+    }
+    /// Run the handler's [validation](CustomInputHandler::validate) on the text, keeping the error if it fails. Returns whether the text is valid.
+    pub fn validate(&mut self) -> bool {
+        match self.custom_input.validate(&self.text_data) {
+            Ok(()) => {
+                self.validation_error = None;
+                true
+            }
+            Err(error) => {
+                self.validation_error = Some(error);
+                false
+            }
+        }
+    }
+    /// Take how the input finished, if it was stopped or submitted since the last call
+    pub fn take_outcome(&mut self) -> Option<InputOutcome> {
+        self.outcome.take()
+    }
     fn update_cursor(&mut self) -> Result<()> {
-        if !self.cursor_within_screen()? {
+        if self.viewer.is_some() || !self.cursor_within_screen()? {
             queue!(stdout(), cursor::Hide)?;
             return Ok(());
         }
         let terminal_size = self.get_terminal_size()?;
         let input_transform = self.get_input_transform()?;
 
-        let x =
-            self.text_data.cursor_x as i16 + input_transform.offset.0 as i16 - self.scroll_x as i16;
+        let cursor_column = self.get_cursor_column();
+        let x = cursor_column as i16 + input_transform.offset.0 as i16 - self.scroll_x as i16;
         let x: u16 = cmp::max(x, 0_i16) as u16;
         let x = cmp::min(x, input_transform.offset.0 + input_transform.size.0);
         let target_y = (self.text_data.cursor_y as u16) + input_transform.offset.1;
@@ -390,30 +915,122 @@ impl<H: CustomInputHandler> CoolInput<H> {
     }
     fn update_text(&mut self) -> Result<()> {
         let terminal_size = self.get_terminal_size()?;
+        let full_transform = self.get_full_input_transform()?;
+        // Read the lines on screen first, as that finds more lines of the viewer and may widen the gutter
+        let visible_lines: Vec<String> = match &mut self.viewer {
+            Some(viewer) => viewer
+                .get_lines(self.scroll_y, full_transform.size.1 as usize)?
+                .to_vec(),
+            None => (self.scroll_y..self.scroll_y + full_transform.size.1 as usize)
+                .map_while(|y| self.text_data.get_line_at(y).map(str::to_string))
+                .collect(),
+        };
         let input_transform = self.get_input_transform()?;
 
         self.custom_input.before_draw_text(HandlerContext {
             text_data: &mut self.text_data,
             terminal_size: &terminal_size,
         });
+        let highlights = self.get_highlights(&terminal_size);
 
-        let offset_y = input_transform.offset.1 as i16;
-        for y in offset_y..offset_y + (input_transform.size.1 as i16) {
-            let y_line_index = y - offset_y + (self.scroll_y as i16);
-            if y_line_index >= 0 && y_line_index < (self.text_data.text.lines().count() as i16) {
-                if let Some(line) = self.text_data.get_line_at(y_line_index as usize) {
-                    let text = get_slice_of_string(
+        let amt_lines = self.get_amt_lines();
+        let offset_y = input_transform.offset.1;
+        // Only clear the input's own width, so inputs sharing a row (like fields of a form) don't clear each other
+        let clear_width = cmp::min(
+            full_transform.size.0,
+            terminal_size.0.saturating_sub(full_transform.offset.0),
+        ) as usize;
+        for y in offset_y..offset_y + input_transform.size.1 {
+            set_terminal_line(
+                &" ".repeat(clear_width),
+                full_transform.offset.0 as usize,
+                y as usize,
+                false,
+            )?;
+            queue!(stdout(), cursor::MoveTo(full_transform.offset.0, y))?;
+            let y_line_index = (y - offset_y) as usize + self.scroll_y;
+            if full_transform.offset.0 < input_transform.offset.0 {
+                self.draw_gutter(y_line_index, amt_lines, &terminal_size)?;
+                queue!(stdout(), cursor::MoveTo(input_transform.offset.0, y))?;
+            }
+            if y_line_index < amt_lines {
+                if let Some(line) = visible_lines.get(y_line_index - self.scroll_y) {
+                    let line_highlights: Vec<&Highlight> = highlights
+                        .iter()
+                        .filter(|highlight| highlight.line == y_line_index)
+                        .collect();
+                    let mut layout = layout_line(
                         line,
-                        self.scroll_x,
+                        &line_highlights,
+                        self.text_data.mask,
+                        self.text_data.tab_width,
+                    );
+                    // Show highlights that cover the end of the line, like extra cursors or selected line breaks
+                    let line_end = line.chars().count();
+                    let end_style = line_highlights
+                        .iter()
+                        .filter(|highlight| highlight.start <= line_end && line_end < highlight.end)
+                        .fold(ContentStyle::new(), |mut style, highlight| {
+                            merge_style(&mut style, &highlight.style);
+                            style
+                        });
+                    if self.text_data.mask.is_none() && end_style != ContentStyle::new() {
+                        layout.cells.push(Cell {
+                            c: ' ',
+                            style: end_style,
+                        });
+                    }
+                    let end = cmp::min(
+                        layout.cells.len(),
                         self.scroll_x + input_transform.size.0 as usize,
                     );
-                    set_terminal_line(&text, input_transform.offset.0 as usize, y as usize, true)?;
+                    if self.scroll_x < end {
+                        self.draw_cells(&layout.cells[self.scroll_x..end], &terminal_size)?;
+                    }
                 }
-            } else {
-                set_terminal_line("", input_transform.offset.0 as usize, y as usize, true)?;
             }
         }
 
+        // Show the placeholder while there is no text
+        let placeholder = match &self.placeholder {
+            Some(placeholder) if self.text_data.text.is_empty() && self.viewer.is_none() => {
+                Some(placeholder)
+            }
+            _ => None,
+        };
+        if let Some(placeholder) = placeholder {
+            let placeholder: String = placeholder
+                .chars()
+                .take(input_transform.size.0 as usize)
+                .collect();
+            let style = ContentStyle {
+                foreground_color: Some(Color::DarkGrey),
+                ..ContentStyle::new()
+            };
+            queue!(
+                stdout(),
+                cursor::MoveTo(input_transform.offset.0, input_transform.offset.1),
+                PrintStyledContent(style.apply(placeholder))
+            )?;
+        }
+
+        // Prompts and validation errors are always shown, on the last row if there is no status row
+        let shown_anyway =
+            self.search.is_some() || self.jump_prompt.is_some() || self.validation_error.is_some();
+        let last_row = terminal::size()?.1.saturating_sub(1);
+        match self.status_row {
+            Some(status_row) => self.draw_status_row(status_row)?,
+            None if self.status_on_last_row || shown_anyway => {
+                self.draw_status_row(last_row)?;
+                self.status_on_last_row_drawn = true;
+            }
+            None if self.status_on_last_row_drawn => {
+                set_terminal_line("", 0, last_row as usize, true)?;
+                self.status_on_last_row_drawn = false;
+            }
+            None => {}
+        }
+
         self.custom_input.after_draw_text(HandlerContext {
             text_data: &mut self.text_data,
             terminal_size: &terminal_size,
@@ -421,11 +1038,172 @@ impl<H: CustomInputHandler> CoolInput<H> {
 
         Ok(())
     }
+    // Get the handler's highlights, followed by the highlights of the annotations, selections, extra cursors and search matches
+    fn get_highlights(&mut self, terminal_size: &(u16, u16)) -> Vec<Highlight> {
+        let mut highlights = self.custom_input.get_highlights(HandlerContext {
+            text_data: &mut self.text_data,
+            terminal_size,
+        });
+        for annotation in self.text_data.annotations.iter() {
+            for line in annotation.start.1..=annotation.end.1 {
+                let line_length = self
+                    .text_data
+                    .text
+                    .split('\n')
+                    .nth(line)
+                    .map_or(0, |l| l.chars().count());
+                highlights.extend(annotation.highlight_line(line, line_length));
+            }
+        }
+        highlights.extend(self.text_data.get_cursor_highlights());
+        if self.viewer.is_some() {
+            highlights.extend(self.get_viewer_search_highlights());
+        } else if self.search.is_some() {
+            let height = self.get_input_transform().map_or(0, |t| t.size.1 as usize);
+            highlights.extend(self.get_search_highlights(self.scroll_y, self.scroll_y + height));
+        }
+        highlights
+    }
+    // Draw the search or jump prompt, the validation error, or the message of the annotation under the cursor
+    fn draw_status_row(&mut self, status_row: u16) -> Result<()> {
+        set_terminal_line("", 0, status_row as usize, true)?;
+        if self.search.is_some() {
+            self.draw_search_prompt()?;
+        } else if let Some(text) = self.get_jump_prompt_text() {
+            print!("{text}");
+        } else if let Some(error) = &self.validation_error {
+            let style = ContentStyle {
+                foreground_color: Some(Severity::Error.color()),
+                ..ContentStyle::new()
+            };
+            queue!(stdout(), PrintStyledContent(style.apply(error)))?;
+        } else if let Some(annotation) = self.get_annotation_at_cursor() {
+            let text = format!("{}: {}", annotation.severity.name(), annotation.message);
+            let style = ContentStyle {
+                foreground_color: Some(annotation.severity.color()),
+                ..ContentStyle::new()
+            };
+            queue!(stdout(), PrintStyledContent(style.apply(text)))?;
+        }
+        Ok(())
+    }
+    // Draw the gutter for a line at the terminal cursor
+    fn draw_gutter(
+        &mut self,
+        y_line_index: usize,
+        amt_lines: usize,
+        terminal_size: &(u16, u16),
+    ) -> Result<()> {
+        let line = (y_line_index < amt_lines).then_some(y_line_index);
+        let sign = match line {
+            Some(line) if self.gutter.sign_column => self
+                .custom_input
+                .get_sign(
+                    line,
+                    HandlerContext {
+                        text_data: &mut self.text_data,
+                        terminal_size,
+                    },
+                )
+                .or_else(|| {
+                    // Fall back to the sign of the most severe annotation on the line
+                    self.text_data
+                        .annotations
+                        .iter()
+                        .filter(|annotation| annotation.start.1 <= line && line <= annotation.end.1)
+                        .map(|annotation| annotation.severity)
+                        .max()
+                        .map(|severity| severity.sign())
+                }),
+            _ => None,
+        };
+        let cells = self
+            .gutter
+            .cells(line, self.text_data.cursor_y, amt_lines, sign);
+        self.draw_cells(&cells, terminal_size)
+    }
+    // Draw cells at the terminal cursor, grouping characters of the same style together
+    fn draw_cells(&mut self, cells: &[Cell], terminal_size: &(u16, u16)) -> Result<()> {
+        let mut start = 0;
+        while start < cells.len() {
+            let style = cells[start].style;
+            let mut end = start;
+            let mut text = String::new();
+            while end < cells.len() && cells[end].style == style {
+                text.push(cells[end].c);
+                end += 1;
+            }
+            if style == ContentStyle::new() {
+                print!("{text}");
+            } else {
+                queue!(stdout(), PrintStyledContent(style.apply(text)))?;
+                // Restore the style of the text
+                self.custom_input.before_draw_text(HandlerContext {
+                    text_data: &mut self.text_data,
+                    terminal_size,
+                });
+            }
+            start = end;
+        }
+        Ok(())
+    }
+    /// Get the position (x, y) in the text that is drawn at a terminal cell, if it is in the text area
+    pub fn get_text_position(&mut self, column: u16, row: u16) -> Result<Option<(usize, usize)>> {
+        let input_transform = self.get_input_transform()?;
+        let (Some(x), Some(y)) = (
+            column.checked_sub(input_transform.offset.0),
+            row.checked_sub(input_transform.offset.1),
+        ) else {
+            return Ok(None);
+        };
+        if x >= input_transform.size.0 || y >= input_transform.size.1 {
+            return Ok(None);
+        }
+        let column = x as usize + self.scroll_x;
+        let y = y as usize + self.scroll_y;
+        // Convert the screen column to a character, as tabs take up several columns
+        let line = self.text_data.get_line_at(y).unwrap_or("");
+        let layout = layout_line(line, &[], self.text_data.mask, self.text_data.tab_width);
+        let line_end = layout.cells.len();
+        let x = match layout.columns.iter().rposition(|c| *c <= column) {
+            Some(x) if column < line_end => x,
+            // Past the end of the line, keep counting in virtual space
+            _ => layout.columns.len() - 1 + column.saturating_sub(line_end),
+        };
+        Ok(Some((x, y)))
+    }
+    /// Get the screen column of the cursor, relative to the start of the line
+    fn get_cursor_column(&mut self) -> usize {
+        let terminal_size = self.get_terminal_size().unwrap_or((0, 0));
+        let highlights = self.get_highlights(&terminal_size);
+        let cursor_x = self.text_data.cursor_x;
+        let cursor_y = self.text_data.cursor_y;
+        let line = match self.text_data.get_line_at(cursor_y) {
+            Some(line) => line.to_string(),
+            None => return cursor_x,
+        };
+        let line_highlights: Vec<&Highlight> = highlights
+            .iter()
+            .filter(|highlight| highlight.line == cursor_y)
+            .collect();
+        let layout = layout_line(
+            &line,
+            &line_highlights,
+            self.text_data.mask,
+            self.text_data.tab_width,
+        );
+        layout
+            .columns
+            .get(cursor_x)
+            .copied()
+            .unwrap_or(layout.cells.len())
+    }
     fn scroll_in_view(&mut self, moving_right: bool, moving_down: bool) -> Result<()> {
         let input_transform = self.get_input_transform()?;
+        let cursor_column = self.get_cursor_column();
         self.scroll_x = self.keep_scroll_axis_in_view(
             self.scroll_x,
-            self.text_data.cursor_x,
+            cursor_column,
             input_transform.size.0 as usize,
             moving_right,
         );
@@ -468,9 +1246,41 @@ impl<H: CustomInputHandler> CoolInput<H> {
     }
     /// Handle an event
     pub fn handle_event(&mut self, event: Event) -> Result<()> {
+        let event = match event {
+            Event::Key(key_event) => Event::Key(self.keymap.translate(key_event)),
+            event => event,
+        };
+        self.dispatch_event(event)?;
+        self.notify_changes()?;
+        self.notify_rejected_edit()
+    }
+    fn dispatch_event(&mut self, event: Event) -> Result<()> {
         let terminal_size = self.get_terminal_size()?;
         let old_cursor_x = self.text_data.cursor_x;
         let old_cursor_y = self.text_data.cursor_y;
+        if let Event::Key(_) = event {
+            self.validation_error = None;
+        }
+        // The search and jump prompts take key presses while they are open
+        if let Event::Key(key_event) = event {
+            if key_event.kind == KeyEventKind::Press {
+                if self.search.is_some() && self.viewer.is_some() {
+                    self.handle_viewer_search_key_press(key_event)?;
+                    self.render()?;
+                    return Ok(());
+                }
+                if self.search.is_some() {
+                    self.handle_search_key_press(key_event)?;
+                    self.render()?;
+                    return Ok(());
+                }
+                if self.jump_prompt.is_some() {
+                    self.handle_jump_prompt_key_press(key_event)?;
+                    self.render()?;
+                    return Ok(());
+                }
+            }
+        }
         match self.custom_input.handle_key_press(
             &event,
             HandlerContext {
@@ -488,30 +1298,108 @@ impl<H: CustomInputHandler> CoolInput<H> {
             }
             KeyPressResult::Stop => {
                 self.listening = false;
+                self.outcome = Some(InputOutcome::Cancelled);
+                return Ok(());
+            }
+            KeyPressResult::Submit => {
+                if self.validate() {
+                    self.listening = false;
+                    self.outcome = Some(InputOutcome::Submitted(self.text_data.text.clone()));
+                } else {
+                    self.render()?;
+                }
                 return Ok(());
             }
             KeyPressResult::Continue => match event {
-                Event::Key(key_event) => {
-                    if key_event.kind == KeyEventKind::Press {
-                        self.text_data.handle_key_press(key_event)?;
-                        self.scroll_in_view(
-                            self.text_data.cursor_x > old_cursor_x,
-                            self.text_data.cursor_y > old_cursor_y,
-                        )?;
-                        self.render()?;
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('f'),
+                    modifiers: KeyModifiers::CONTROL,
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    self.open_search();
+                    self.render()?;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('g'),
+                    modifiers: KeyModifiers::CONTROL,
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    self.jump_prompt = Some(JumpPrompt::GoToLine(String::new()));
+                    self.render()?;
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char(c @ ('m' | 'j')),
+                    modifiers: KeyModifiers::ALT,
+                    kind: KeyEventKind::Press,
+                    ..
+                }) if self.viewer.is_none() => {
+                    self.jump_prompt = Some(if c == 'm' {
+                        JumpPrompt::SetMark
+                    } else {
+                        JumpPrompt::GoToMark
+                    });
+                    self.render()?;
+                }
+                // The viewer is read-only, so keys only scroll it
+                Event::Key(key_event)
+                    if key_event.kind == KeyEventKind::Press && self.viewer.is_some() =>
+                {
+                    self.handle_viewer_key_press(key_event)?;
+                    self.render()?;
+                }
+                Event::Paste(_) if self.viewer.is_some() => {}
+                Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
+                    self.text_data.handle_key_press(key_event)?;
+                    self.scroll_in_view(
+                        self.text_data.cursor_x > old_cursor_x,
+                        self.text_data.cursor_y > old_cursor_y,
+                    )?;
+                    self.render()?;
+                }
+                Event::Paste(text) => {
+                    self.text_data.block_to_cursors(true)?;
+                    if self.text_data.block_selection.is_none() {
+                        self.text_data
+                            .for_each_cursor(|text_data| text_data.write_str(&text))?;
                     }
+                    self.scroll_in_view(
+                        self.text_data.cursor_x > old_cursor_x,
+                        self.text_data.cursor_y > old_cursor_y,
+                    )?;
+                    self.render()?;
                 }
                 Event::Mouse(mouse_event) => match mouse_event.kind {
+                    MouseEventKind::Down(MouseButton::Left)
+                    | MouseEventKind::Drag(MouseButton::Left)
+                        if mouse_event.modifiers.contains(KeyModifiers::ALT)
+                            && self.viewer.is_none() =>
+                    {
+                        let Some((x, y)) =
+                            self.get_text_position(mouse_event.column, mouse_event.row)?
+                        else {
+                            return Ok(());
+                        };
+                        // Blocks are in screen columns, not characters
+                        let position = (self.text_data.column_of(x, y), y);
+                        let anchor = match (mouse_event.kind, self.text_data.block_selection) {
+                            (MouseEventKind::Drag(_), Some(block)) => block.anchor,
+                            _ => position,
+                        };
+                        self.text_data.select_block(anchor, position);
+                        self.render()?;
+                    }
                     MouseEventKind::ScrollUp => {
                         self.scroll_y = self.scroll_y.saturating_sub(1);
                         self.render()?;
                     }
                     MouseEventKind::ScrollDown => {
                         let input_transform = self.get_input_transform()?;
-                        let content_ends_y = self.text_data.text.split('\n').count() as u16
-                            + input_transform.offset.1;
+                        let content_ends_y =
+                            self.get_amt_lines() + input_transform.offset.1 as usize;
                         let (_, height) = self.get_terminal_size()?;
-                        let screen_ends_y = height + self.scroll_y as u16;
+                        let screen_ends_y = height as usize + self.scroll_y;
                         if screen_ends_y <= content_ends_y {
                             self.scroll_y += 1;
                             self.render()?;
@@ -525,21 +1413,43 @@ impl<H: CustomInputHandler> CoolInput<H> {
         Ok(())
     }
     /// Start listening for key presses without preparing the terminal
-    pub fn listen_quiet(&mut self) -> Result<()> {
+    pub fn listen_quiet(&mut self) -> Result<InputOutcome> {
         self.listening = true;
+        self.outcome = None;
         while self.listening {
-            self.handle_event(event::read()?)?;
+            match self.tick_rate {
+                Some(tick_rate) => {
+                    if event::poll(tick_rate)? {
+                        self.handle_event(event::read()?)?;
+                    }
+                    if self.listening {
+                        self.tick()?;
+                    }
+                }
+                None => self.handle_event(event::read()?)?,
+            }
         }
-        Ok(())
+        Ok(self.outcome.take().unwrap_or(InputOutcome::Cancelled))
+    }
+    /// Call the handler's [on_tick](CustomInputHandler::on_tick), and render if it asks to. Edits it made are passed to [on_change](CustomInputHandler::on_change).
+    pub fn tick(&mut self) -> Result<()> {
+        let terminal_size = self.get_terminal_size()?;
+        if self.custom_input.on_tick(HandlerContext {
+            text_data: &mut self.text_data,
+            terminal_size: &terminal_size,
+        }) {
+            self.render()?;
+        }
+        self.notify_changes()
     }
     /// Prepare the terminal for input
     pub fn pre_listen(&mut self) -> Result<()> {
+        // Edits made while setting up the input aren't reported to the handler
+        self.text_data.take_edits();
         let input_transform = self.get_input_transform()?;
-        enable_raw_mode()?;
+        prepare_terminal_with(self.screen_mode, self.mouse_capture)?;
         execute!(
             stdout(),
-            EnableMouseCapture,
-            terminal::Clear(terminal::ClearType::All),
             cursor::MoveTo(
                 (self.text_data.cursor_x as u16) + input_transform.offset.0,
                 (self.text_data.cursor_y as u16) + input_transform.offset.1
@@ -549,23 +1459,38 @@ impl<H: CustomInputHandler> CoolInput<H> {
     }
     /// Restore the terminal after input is finished.
     pub fn post_listen(&mut self) -> Result<()> {
-        execute!(
-            stdout(),
-            ResetColor,
-            DisableMouseCapture,
-            terminal::Clear(terminal::ClearType::All),
-            cursor::MoveTo(0, 0),
-            cursor::Show,
-        )?;
-        disable_raw_mode()?;
-        Ok(())
+        restore_terminal_with(self.screen_mode, self.mouse_capture)
     }
     /// Prepare terminal and start to listen for key presses until finished.
-    pub fn listen(&mut self) -> Result<()> {
+    pub fn listen(&mut self) -> Result<InputOutcome> {
         self.pre_listen()?;
         self.render()?;
-        self.listen_quiet()?;
+        let outcome = self.listen_quiet()?;
         self.post_listen()?;
-        Ok(())
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn up_and_down_keep_the_screen_column() {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text("\tx\nabcdef\nab");
+        (text_data.cursor_x, text_data.cursor_y) = (4, 1);
+        text_data.move_cursor_up().unwrap();
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (1, 0));
+        text_data.move_cursor_down().unwrap();
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (4, 1));
+        // Inside a tab, the cursor stays on it
+        text_data.cursor_x = 2;
+        text_data.move_cursor_up().unwrap();
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (0, 0));
+        text_data.cursor_x = 2;
+        text_data.move_cursor_down().unwrap();
+        text_data.move_cursor_down().unwrap();
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (2, 2));
     }
 }
@@ -0,0 +1,113 @@
+use crate::TextInputData;
+
+/// How lines end in a document. The text of a [TextInputData] always uses `\n`, and the line ending is applied when the text is written out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineEnding {
+    /// `\n`, used on Unix
+    #[default]
+    Lf,
+    /// `\r\n`, used on Windows
+    CrLf,
+}
+
+impl LineEnding {
+    /// Detect the line ending of a text, by which one it uses most. Texts without line breaks are [Lf](LineEnding::Lf).
+    pub fn detect(text: &str) -> LineEnding {
+        let amt_lines = text.matches('\n').count();
+        let amt_crlf = text.matches("\r\n").count();
+        if amt_crlf * 2 > amt_lines {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        }
+    }
+    /// Whether a text uses both `\r\n` and `\n` line breaks. Writing it out with one [line ending](LineEnding) converts the others.
+    pub fn is_mixed(text: &str) -> bool {
+        let amt_crlf = text.matches("\r\n").count();
+        amt_crlf > 0 && amt_crlf < text.matches('\n').count()
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+    /// Convert every line break of a text to `\n`
+    pub fn normalize(text: &str) -> String {
+        text.replace("\r\n", "\n")
+    }
+    /// Convert every `\n` of a text to this line ending
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            LineEnding::Lf => text.to_string(),
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+impl TextInputData {
+    /// Replace the text with one that was ex. read from a file. Its [line ending](LineEnding) is detected and kept to [write it back](TextInputData::get_text_with_line_endings),
+    /// while the text itself is stored with `\n` line breaks. The cursor is moved to the start.
+    /// The text is loaded as is, even if it breaks the [constraints](crate::InputConstraints), is [read-only](TextInputData::read_only) or has [protected ranges](TextInputData::protected_ranges).
+    ///
+    /// The [undo history](TextInputData::undo_history) is cleared, as it belongs to the text before.
+    ///
+    /// If the text [mixes line endings](LineEnding::is_mixed), [mixed_line_endings](TextInputData::mixed_line_endings) is set, as they will all be written out as the detected one.
+    pub fn load_text(&mut self, text: &str) {
+        self.line_ending = LineEnding::detect(text);
+        self.mixed_line_endings = LineEnding::is_mixed(text);
+        let removed = std::mem::replace(&mut self.text, LineEnding::normalize(text));
+        self.revision += 1;
+        self.record_replace_all(removed);
+        let edits = self.drain_pending_edits();
+        self.finished_edits.extend(edits);
+        self.undo_history.clear();
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        self.selection_anchor = None;
+        self.block_selection = None;
+        self.extra_cursors.clear();
+    }
+    /// Get the text with the document's [line ending](TextInputData::line_ending), ex. to save it to a file
+    pub fn get_text_with_line_endings(&self) -> String {
+        self.line_ending.apply(&self.text)
+    }
+    /// Convert the document to another line ending. Takes effect when the text is [written out](TextInputData::get_text_with_line_endings).
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        assert_eq!(LineEnding::detect("no line breaks"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a\nb\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a\r\nb\r\n"), LineEnding::CrLf);
+        // The most used one wins
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\n"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\r\nb\nc\n"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn is_mixed() {
+        assert!(!LineEnding::is_mixed("a\nb\n"));
+        assert!(!LineEnding::is_mixed("a\r\nb\r\n"));
+        assert!(!LineEnding::is_mixed("a"));
+        assert!(LineEnding::is_mixed("a\r\nb\n"));
+    }
+
+    #[test]
+    fn load_and_write_out() {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text("a\r\nb\r\nc\n");
+        assert_eq!(text_data.text, "a\nb\nc\n");
+        assert_eq!(text_data.line_ending, LineEnding::CrLf);
+        assert!(text_data.mixed_line_endings);
+        assert_eq!(text_data.get_text_with_line_endings(), "a\r\nb\r\nc\r\n");
+    }
+}
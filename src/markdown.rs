@@ -0,0 +1,252 @@
+use crate::highlight::Highlight;
+use crate::{CustomInputHandler, HandlerContext, KeyPressResult};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, ContentStyle};
+use crossterm::{cursor, queue};
+use std::cmp;
+use std::io::stdout;
+
+fn style(color: Color) -> ContentStyle {
+    ContentStyle {
+        foreground_color: Some(color),
+        ..ContentStyle::new()
+    }
+}
+
+fn style_with(color: Color, attribute: Attribute) -> ContentStyle {
+    let mut style = style(color);
+    style.attributes.set(attribute);
+    style
+}
+
+// Style of markup characters (`#`, `*`, backticks..), or conceal them in preview mode
+fn markup(line: usize, start: usize, end: usize, conceal: bool) -> Highlight {
+    if conceal {
+        Highlight::concealed(line, start, end)
+    } else {
+        Highlight::new(line, start, end, style(Color::DarkGrey))
+    }
+}
+
+// Find the index of the next occurrence of `pattern` in `chars`, starting at `from`
+fn find(chars: &[char], pattern: &[char], from: usize) -> Option<usize> {
+    if pattern.len() > chars.len() {
+        return None;
+    }
+    (from..=chars.len() - pattern.len()).find(|&i| chars[i..i + pattern.len()] == *pattern)
+}
+
+// Length of the list marker (including the trailing space) at the start of the line, if any
+fn list_marker_length(chars: &[char]) -> Option<usize> {
+    match chars {
+        ['-' | '*' | '+', ' ', ..] => Some(2),
+        _ => {
+            let digits = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+            if digits > 0 && chars[digits..].starts_with(&['.', ' ']) {
+                Some(digits + 2)
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// Highlight code spans, emphasis and links in a line, starting at character `start`
+fn highlight_inline(
+    highlights: &mut Vec<Highlight>,
+    y: usize,
+    chars: &[char],
+    start: usize,
+    conceal: bool,
+) {
+    let mut i = start;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '`' {
+            if let Some(end) = find(chars, &['`'], i + 1) {
+                highlights.push(markup(y, i, i + 1, conceal));
+                highlights.push(Highlight::new(y, i + 1, end, style(Color::DarkYellow)));
+                highlights.push(markup(y, end, end + 1, conceal));
+                i = end + 1;
+                continue;
+            }
+        } else if c == '[' {
+            if let Some(middle) = find(chars, &[']', '('], i + 1) {
+                if let Some(end) = find(chars, &[')'], middle + 2) {
+                    highlights.push(markup(y, i, i + 1, conceal));
+                    highlights.push(Highlight::new(
+                        y,
+                        i + 1,
+                        middle,
+                        style_with(Color::Blue, Attribute::Underlined),
+                    ));
+                    highlights.push(markup(y, middle, end + 1, conceal));
+                    i = end + 1;
+                    continue;
+                }
+            }
+        } else if c == '*' || c == '_' {
+            // Underscores inside of words (like snake_case) aren't emphasis
+            let inside_word = c == '_' && i > 0 && chars[i - 1].is_alphanumeric();
+            let amt = if chars.get(i + 1) == Some(&c) { 2 } else { 1 };
+            let delimiter = &chars[i..i + amt];
+            let opens = chars.get(i + amt).is_some_and(|next| !next.is_whitespace());
+            if !inside_word && opens {
+                if let Some(end) = find(chars, delimiter, i + amt + 1) {
+                    let attribute = if amt == 2 {
+                        Attribute::Bold
+                    } else {
+                        Attribute::Italic
+                    };
+                    let mut text_style = ContentStyle::new();
+                    text_style.attributes.set(attribute);
+                    highlights.push(markup(y, i, i + amt, conceal));
+                    highlights.push(Highlight::new(y, i + amt, end, text_style));
+                    highlights.push(markup(y, end, end + amt, conceal));
+                    i = end + amt;
+                    continue;
+                }
+            }
+            i += amt;
+            continue;
+        }
+        i += 1;
+    }
+}
+
+/// Highlight Markdown text: headings, emphasis, code spans, fenced code blocks, lists, block quotes and links.
+///
+/// If `conceal` is true, the markup characters are hidden, so the text is rendered like a preview of the document.
+pub fn highlight_markdown(text: &str, conceal: bool) -> Vec<Highlight> {
+    let mut highlights = Vec::new();
+    let mut in_code_block = false;
+
+    for (y, line) in text.split('\n').enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let indent = chars.iter().take_while(|c| **c == ' ').count();
+        let content = &chars[indent..];
+
+        if content.starts_with(&['`', '`', '`']) {
+            in_code_block = !in_code_block;
+            highlights.push(markup(y, 0, chars.len(), conceal));
+            continue;
+        }
+        if in_code_block {
+            highlights.push(Highlight::new(y, 0, chars.len(), style(Color::DarkYellow)));
+            continue;
+        }
+
+        let level = content.iter().take_while(|c| **c == '#').count();
+        if (1..=6).contains(&level) && matches!(content.get(level), Some(' ') | None) {
+            let color = if level == 1 {
+                Color::Magenta
+            } else {
+                Color::Blue
+            };
+            let prefix_end = cmp::min(indent + level + 1, chars.len());
+            highlights.push(Highlight::new(
+                y,
+                0,
+                chars.len(),
+                style_with(color, Attribute::Bold),
+            ));
+            highlights.push(markup(y, 0, prefix_end, conceal));
+            highlight_inline(&mut highlights, y, &chars, prefix_end, conceal);
+            continue;
+        }
+
+        if content.starts_with(&['>']) {
+            let prefix_end = if content.starts_with(&['>', ' ']) {
+                indent + 2
+            } else {
+                indent + 1
+            };
+            highlights.push(Highlight::new(
+                y,
+                prefix_end,
+                chars.len(),
+                style_with(Color::Grey, Attribute::Italic),
+            ));
+            highlights.push(Highlight::new(
+                y,
+                indent,
+                prefix_end,
+                style(Color::DarkGrey),
+            ));
+            highlight_inline(&mut highlights, y, &chars, prefix_end, conceal);
+            continue;
+        }
+
+        let mut inline_start = indent;
+        if let Some(length) = list_marker_length(content) {
+            highlights.push(Highlight::new(
+                y,
+                indent,
+                indent + length - 1,
+                style_with(Color::Yellow, Attribute::Bold),
+            ));
+            inline_start += length;
+        }
+        highlight_inline(&mut highlights, y, &chars, inline_start, conceal);
+    }
+    highlights
+}
+
+/// An input handler for editing Markdown documents.
+///
+/// Highlights the Markdown syntax, and toggles a read-only preview with the markup concealed on CTRL + P.
+pub struct MarkdownInputHandler {
+    pub preview: bool,
+}
+
+impl MarkdownInputHandler {
+    pub fn new() -> Self {
+        MarkdownInputHandler { preview: false }
+    }
+}
+
+impl Default for MarkdownInputHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomInputHandler for MarkdownInputHandler {
+    fn handle_key_press(&mut self, key: &Event, _: HandlerContext) -> KeyPressResult {
+        // Pasting would edit the read-only preview too
+        if matches!(key, Event::Paste(_)) && self.preview {
+            return KeyPressResult::Handled;
+        }
+        if let Event::Key(key_event) = key {
+            if key_event.kind == KeyEventKind::Press {
+                let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+                match key_event.code {
+                    KeyCode::Esc => return KeyPressResult::Stop,
+                    KeyCode::Char('c') if control => return KeyPressResult::Stop,
+                    KeyCode::Char('p') if control => {
+                        self.preview = !self.preview;
+                        return KeyPressResult::Handled;
+                    }
+                    // The preview is read-only, so only let movement keys through
+                    KeyCode::Up
+                    | KeyCode::Down
+                    | KeyCode::Left
+                    | KeyCode::Right
+                    | KeyCode::Home
+                    | KeyCode::End => {}
+                    _ if self.preview => return KeyPressResult::Handled,
+                    _ => {}
+                }
+            }
+        }
+        KeyPressResult::Continue
+    }
+    fn get_highlights(&mut self, ctx: HandlerContext) -> Vec<Highlight> {
+        highlight_markdown(&ctx.text_data.text, self.preview)
+    }
+    fn after_update_cursor(&mut self, _: HandlerContext) {
+        if self.preview {
+            let _ = queue!(stdout(), cursor::Hide);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::anchor;
+use crate::cursors::key;
+use crate::search::TextRange;
+use crate::{CoolInput, CustomInputHandler, HandlerContext, TextInputData};
+use std::io::Result;
+
+impl TextInputData {
+    /// Whether a character may be inserted at (x, y). Text can be inserted at the edges of a [protected range](TextInputData::protected_ranges), but not inside it.
+    pub fn can_insert_at(&self, x: usize, y: usize) -> bool {
+        !self.read_only
+            && !self
+                .protected_ranges
+                .iter()
+                .any(|(start, end)| key(*start) < key((x, y)) && key((x, y)) < key(*end))
+    }
+    /// Whether the character before (x, y) may be removed
+    pub fn can_remove_before(&self, x: usize, y: usize) -> bool {
+        !self.read_only
+            && !self
+                .protected_ranges
+                .iter()
+                .any(|(start, end)| key(*start) < key((x, y)) && key((x, y)) <= key(*end))
+    }
+    /// Whether the text between two positions may be removed, ie. none of it is [protected](TextInputData::protected_ranges)
+    pub fn can_remove_between(&self, start: (usize, usize), end: (usize, usize)) -> bool {
+        !self.read_only
+            && !self
+                .protected_ranges
+                .iter()
+                .any(|(range_start, range_end)| {
+                    key(start) < key(*range_end) && key(*range_start) < key(end)
+                })
+    }
+    /// Take the position of the first edit that was rejected since the last call, because the text is [read-only](TextInputData::read_only) or [protected](TextInputData::protected_ranges)
+    pub fn take_rejected_edit(&mut self) -> Option<(usize, usize)> {
+        self.rejected_edit.take()
+    }
+    // Remember a rejected edit, to notify the handler once the event is handled
+    pub(crate) fn reject_edit(&mut self, x: usize, y: usize) {
+        self.rejected_edit.get_or_insert((x, y));
+    }
+    pub(crate) fn shift_protected_ranges_on_insert(&mut self, c: char, x: usize, y: usize) {
+        for (start, end) in self.protected_ranges.iter_mut() {
+            anchor::shift_on_insert(start, c, x, y, true);
+            anchor::shift_on_insert(end, c, x, y, false);
+        }
+    }
+    pub(crate) fn shift_protected_ranges_on_remove(
+        &mut self,
+        x: usize,
+        y: usize,
+        previous_line_length: usize,
+    ) {
+        for (start, end) in self.protected_ranges.iter_mut() {
+            anchor::shift_on_remove(start, x, y, previous_line_length);
+            anchor::shift_on_remove(end, x, y, previous_line_length);
+        }
+    }
+}
+
+impl<H: CustomInputHandler> CoolInput<H> {
+    /// Protect the text between two positions from being edited, ex. a fixed header of a template
+    pub fn add_protected_range(&mut self, start: (usize, usize), end: (usize, usize)) {
+        let range: TextRange = crate::cursors::order(start, end);
+        self.text_data.protected_ranges.push(range);
+    }
+    // Tell the handler about an edit that was rejected while handling an event
+    pub(crate) fn notify_rejected_edit(&mut self) -> Result<()> {
+        let Some(position) = self.text_data.take_rejected_edit() else {
+            return Ok(());
+        };
+        let terminal_size = self.get_terminal_size()?;
+        if self.custom_input.on_edit_rejected(
+            position,
+            HandlerContext {
+                text_data: &mut self.text_data,
+                terminal_size: &terminal_size,
+            },
+        ) {
+            self.render()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protected_text() -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text("hello KEEP world");
+        text_data.protected_ranges.push(((6, 0), (10, 0)));
+        text_data
+    }
+
+    #[test]
+    fn insert_only_at_edges() {
+        let text_data = protected_text();
+        assert!(text_data.can_insert_at(6, 0));
+        assert!(!text_data.can_insert_at(8, 0));
+        assert!(text_data.can_insert_at(10, 0));
+    }
+
+    #[test]
+    fn removal_overlapping_protected_text_is_rejected_whole() {
+        let mut text_data = protected_text();
+        assert!(!text_data.remove_between((2, 0), (13, 0)).unwrap());
+        assert_eq!(text_data.text, "hello KEEP world");
+        assert_eq!(text_data.take_rejected_edit(), Some((2, 0)));
+        assert!(text_data
+            .take_edits()
+            .iter()
+            .all(|edit| edit.removed.is_empty()));
+
+        assert!(text_data.remove_between((0, 0), (6, 0)).unwrap());
+        assert_eq!(text_data.text, "KEEP world");
+        assert_eq!(text_data.protected_ranges, vec![((0, 0), (4, 0))]);
+        assert_eq!(text_data.take_rejected_edit(), None);
+    }
+
+    #[test]
+    fn replace_all_skips_protected_matches() {
+        let mut text_data = protected_text();
+        text_data.load_text("a KEEP a");
+        text_data.protected_ranges = vec![((2, 0), (6, 0))];
+        assert_eq!(text_data.replace_all("E", "x").unwrap(), 0);
+        assert_eq!(text_data.replace_all("a", "bb").unwrap(), 2);
+        assert_eq!(text_data.text, "bb KEEP bb");
+    }
+
+    #[test]
+    fn replace_text_is_an_edit() {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(
+            "old
+text",
+        );
+        text_data.take_edits();
+        assert!(text_data.replace_text("new").unwrap());
+        assert_eq!(text_data.text, "new");
+        let edits = text_data.take_edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].removed, "old\ntext");
+        assert_eq!(edits[0].inserted, "new");
+
+        let mut text_data = protected_text();
+        assert!(!text_data.replace_text("gone").unwrap());
+        assert_eq!(text_data.text, "hello KEEP world");
+    }
+
+    #[test]
+    fn read_only_rejects_edits() {
+        let mut input = crate::CoolInputBuilder::new(crate::DefaultInputHandler)
+            .text("fixed")
+            .cursor_at_end()
+            .read_only(true)
+            .build()
+            .unwrap();
+        let text_data = &mut input.text_data;
+        assert!(!text_data.write_char('x').unwrap());
+        assert!(!text_data.remove_character(5, 0).unwrap());
+        assert_eq!(text_data.text, "fixed");
+        assert_eq!(text_data.take_rejected_edit(), Some((5, 0)));
+        // Loading text still works
+        text_data.load_text("loaded");
+        assert_eq!(text_data.text, "loaded");
+    }
+}
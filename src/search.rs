@@ -0,0 +1,619 @@
+use crate::annotation::Severity;
+use crate::highlight::Highlight;
+use crate::{CoolInput, CustomInputHandler, TextInputData};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::{Color, ContentStyle, PrintStyledContent};
+#[cfg(feature = "regex")]
+use std::cell::RefCell;
+use std::io::{stdout, Result};
+use std::ops::Range;
+
+/// A match in the text, from its start to its end position
+pub type TextRange = ((usize, usize), (usize, usize));
+
+// Convert a byte range of a line to a range of character positions
+pub(crate) fn char_range(y: usize, line: &str, range: Range<usize>) -> TextRange {
+    let start = line[..range.start].chars().count();
+    let end = start + line[range].chars().count();
+    ((start, y), (end, y))
+}
+
+/// Finds the byte ranges of the matches in a line
+pub(crate) type LineMatcher = dyn Fn(&str) -> Vec<Range<usize>>;
+
+/// The regex compiled from a query, kept until the query changes
+#[cfg(feature = "regex")]
+#[derive(Clone, Debug, Default)]
+struct RegexCache(RefCell<Option<(String, std::result::Result<regex::Regex, String>)>>);
+
+/// State of the search prompt of a [CoolInput], opened with CTRL + F.
+///
+/// While searching, typing edits the query and every match on screen is highlighted.
+/// Enter and Down go to the next match, Up to the previous one. Tab switches to the replacement,
+/// where Enter replaces the current match and ALT + A replaces all of them. ALT + A switches to the replacement first if none was entered.
+/// Escape closes the prompt.
+#[derive(Clone, Debug, Default)]
+pub struct Search {
+    pub query: String,
+    /// Text to replace matches with. With [regex](Search::regex), `$1` or `${name}` insert capture groups.
+    pub replacement: Option<String>,
+    /// Whether typing goes to the replacement instead of the query
+    pub editing_replacement: bool,
+    /// Treat the query as a regular expression, toggled with ALT + R. Only available with the `regex` feature.
+    pub regex: bool,
+    /// Position the search started from. The first match after it is selected as the query is typed.
+    origin: (usize, usize),
+    /// Byte index of the selected match
+    current: Option<usize>,
+    /// Whether the jump away from the origin was recorded
+    jumped: bool,
+    /// Selected match in a [viewer](CoolInput::viewer), which is searched line by line instead of by byte index
+    pub(crate) found: Option<TextRange>,
+    /// Whether the last search in a viewer found nothing
+    pub(crate) not_found: bool,
+    #[cfg(feature = "regex")]
+    regex_cache: RegexCache,
+}
+
+// Convert sorted, non overlapping byte ranges of the text to positions, walking the text only once
+fn byte_ranges_to_positions(text: &str, ranges: &[Range<usize>]) -> Vec<TextRange> {
+    let mut index = 0;
+    let mut position = (0, 0);
+    let mut walk_to = |target: usize| {
+        for c in text[index..target].chars() {
+            if c == '\n' {
+                position = (0, position.1 + 1);
+            } else {
+                position.0 += 1;
+            }
+        }
+        index = target;
+        position
+    };
+    ranges
+        .iter()
+        .map(|range| (walk_to(range.start), walk_to(range.end)))
+        .collect()
+}
+
+impl TextInputData {
+    /// Find every occurrence of `query` in the text
+    pub fn find_all(&self, query: &str) -> Vec<TextRange> {
+        byte_ranges_to_positions(&self.text, &self.find_all_bytes(query))
+    }
+    /// Find every match of a regular expression in the text
+    #[cfg(feature = "regex")]
+    pub fn find_all_regex(&self, regex: &regex::Regex) -> Vec<TextRange> {
+        byte_ranges_to_positions(&self.text, &self.find_all_regex_bytes(regex))
+    }
+    fn find_all_bytes(&self, query: &str) -> Vec<Range<usize>> {
+        if query.is_empty() {
+            return Vec::new();
+        }
+        self.text
+            .match_indices(query)
+            .map(|(index, found)| index..index + found.len())
+            .collect()
+    }
+    #[cfg(feature = "regex")]
+    fn find_all_regex_bytes(&self, regex: &regex::Regex) -> Vec<Range<usize>> {
+        regex
+            .find_iter(&self.text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+    /// Replace the text between two positions, leaving the cursor after the replacement.
+    /// Characters of the replacement rejected by the [constraints](crate::InputConstraints) are skipped.
+    /// Returns whether it was replaced: nothing changes if the range can't be [removed](TextInputData::remove_between).
+    pub fn replace_range(
+        &mut self,
+        start: (usize, usize),
+        end: (usize, usize),
+        replacement: &str,
+    ) -> Result<bool> {
+        self.selection_anchor = None;
+        if !self.remove_between(start, end)? {
+            return Ok(false);
+        }
+        self.write_str(replacement)?;
+        Ok(true)
+    }
+    /// Replace all of the text, like selecting everything and typing. Unlike [load_text](TextInputData::load_text),
+    /// this is an edit, so [read-only](TextInputData::read_only), [protected](TextInputData::protected_ranges) text and constraints apply.
+    pub fn replace_text(&mut self, text: &str) -> Result<bool> {
+        let last_line = self.text.rsplit('\n').next().unwrap_or("");
+        let end = (last_line.chars().count(), self.text.matches('\n').count());
+        self.replace_range((0, 0), end, text)
+    }
+    /// Replace every occurrence of `query`. Returns the amount of replaced occurrences.
+    pub fn replace_all(&mut self, query: &str, replacement: &str) -> Result<usize> {
+        let matches = self.find_all(query);
+        let mut replaced = 0;
+        // Replace from the end, so positions of earlier matches stay the same
+        for (start, end) in matches.iter().rev() {
+            if self.replace_range(*start, *end, replacement)? {
+                replaced += 1;
+            }
+        }
+        Ok(replaced)
+    }
+    /// Replace every match of a regular expression. `$1` or `${name}` in the replacement insert capture groups.
+    /// Returns the amount of replaced matches.
+    #[cfg(feature = "regex")]
+    pub fn replace_all_regex(&mut self, regex: &regex::Regex, replacement: &str) -> Result<usize> {
+        let ranges = self.find_all_regex_bytes(regex);
+        let replacements: Vec<String> = ranges
+            .iter()
+            .map(|range| expand_captures(regex, &self.text, range.start, replacement))
+            .collect();
+        let matches = byte_ranges_to_positions(&self.text, &ranges);
+        let mut replaced = 0;
+        for ((start, end), replacement) in matches.iter().zip(replacements.iter()).rev() {
+            if self.replace_range(*start, *end, replacement)? {
+                replaced += 1;
+            }
+        }
+        Ok(replaced)
+    }
+}
+
+// Expand the capture groups of the match at `start` into the replacement
+#[cfg(feature = "regex")]
+fn expand_captures(regex: &regex::Regex, text: &str, start: usize, replacement: &str) -> String {
+    let mut expanded = String::new();
+    if let Some(captures) = regex.captures_at(text, start) {
+        captures.expand(replacement, &mut expanded);
+    }
+    expanded
+}
+
+// The regex cache isn't part of the state of a search, so it is left out
+impl PartialEq for Search {
+    fn eq(&self, other: &Self) -> bool {
+        self.query == other.query
+            && self.replacement == other.replacement
+            && self.editing_replacement == other.editing_replacement
+            && self.regex == other.regex
+            && self.origin == other.origin
+            && self.current == other.current
+            && self.jumped == other.jumped
+            && self.found == other.found
+            && self.not_found == other.not_found
+    }
+}
+
+impl Eq for Search {}
+
+impl Search {
+    /// Start a search from a position in the text
+    pub fn new(origin: (usize, usize)) -> Self {
+        Search {
+            origin,
+            ..Search::default()
+        }
+    }
+    // Get the text that typing edits
+    fn active_field(&mut self) -> &mut String {
+        if self.editing_replacement {
+            self.replacement.get_or_insert_with(String::new)
+        } else {
+            &mut self.query
+        }
+    }
+    /// Get the query compiled as a regex, compiling it only when it changed
+    #[cfg(feature = "regex")]
+    fn get_regex(&self) -> std::result::Result<regex::Regex, String> {
+        let mut cache = self.regex_cache.0.borrow_mut();
+        if let Some((query, regex)) = cache.as_ref() {
+            if *query == self.query {
+                return regex.clone();
+            }
+        }
+        let regex = regex::Regex::new(&self.query).map_err(|_| "invalid regex".to_string());
+        *cache = Some((self.query.clone(), regex.clone()));
+        regex
+    }
+    /// Find the byte ranges of every match, or get why the query is invalid
+    fn find_matches(
+        &self,
+        text_data: &TextInputData,
+    ) -> std::result::Result<Vec<Range<usize>>, String> {
+        #[cfg(feature = "regex")]
+        if self.regex {
+            if self.query.is_empty() {
+                return Ok(Vec::new());
+            }
+            return Ok(text_data.find_all_regex_bytes(&self.get_regex()?));
+        }
+        Ok(text_data.find_all_bytes(&self.query))
+    }
+    /// Get a function that finds the byte ranges of the matches in a single line, or why the query is invalid.
+    /// Uses the cached regex, so it is cheap to call on every render.
+    pub(crate) fn line_matcher(&self) -> std::result::Result<Box<LineMatcher>, String> {
+        if self.query.is_empty() {
+            return Ok(Box::new(|_| Vec::new()));
+        }
+        #[cfg(feature = "regex")]
+        if self.regex {
+            let regex = self.get_regex()?;
+            return Ok(Box::new(move |line| {
+                regex
+                    .find_iter(line)
+                    .map(|found| found.range())
+                    .filter(|range| !range.is_empty())
+                    .collect()
+            }));
+        }
+        let query = self.query.clone();
+        Ok(Box::new(move |line| {
+            line.match_indices(query.as_str())
+                .map(|(index, found)| index..index + found.len())
+                .collect()
+        }))
+    }
+    /// Get the replacement for the match at `range`, with capture groups expanded, or `None` if no replacement was entered
+    #[allow(unused_variables)]
+    fn expand_replacement(
+        &self,
+        text_data: &TextInputData,
+        range: &Range<usize>,
+    ) -> Option<String> {
+        let replacement = self.replacement.clone()?;
+        #[cfg(feature = "regex")]
+        if self.regex {
+            if let Ok(regex) = self.get_regex() {
+                return Some(expand_captures(
+                    &regex,
+                    &text_data.text,
+                    range.start,
+                    &replacement,
+                ));
+            }
+        }
+        Some(replacement)
+    }
+}
+
+impl<H: CustomInputHandler> CoolInput<H> {
+    /// Open the search prompt, starting at the cursor. Does nothing while the text is [masked](TextInputData::mask), as matches would reveal it.
+    pub fn open_search(&mut self) {
+        if self.text_data.mask.is_some() {
+            return;
+        }
+        self.search = Some(Search::new((
+            self.text_data.cursor_x,
+            self.text_data.cursor_y,
+        )));
+    }
+    // Handle a key press while the search prompt is open
+    pub(crate) fn handle_search_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
+        if self.text_data.mask.is_some() {
+            self.search = None;
+        }
+        let Some(search) = &mut self.search else {
+            return Ok(());
+        };
+        let alt = key_event.modifiers.contains(KeyModifiers::ALT);
+        let typing = !key_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key_event.code {
+            KeyCode::Esc => {
+                self.search = None;
+                return Ok(());
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                search.editing_replacement = !search.editing_replacement;
+                if search.editing_replacement {
+                    search.replacement.get_or_insert_with(String::new);
+                }
+                return Ok(());
+            }
+            // Without a replacement, ask for one instead of deleting every match
+            KeyCode::Char('a') if alt && search.replacement.is_none() => {
+                search.editing_replacement = true;
+                search.replacement = Some(String::new());
+                return Ok(());
+            }
+            KeyCode::Char('a') if alt => return self.replace_all_matches(),
+            #[cfg(feature = "regex")]
+            KeyCode::Char('r') if alt => {
+                search.regex = !search.regex;
+            }
+            KeyCode::Char(c) if typing => search.active_field().push(c),
+            KeyCode::Backspace => {
+                search.active_field().pop();
+            }
+            KeyCode::Enter if search.editing_replacement => return self.replace_current_match(),
+            KeyCode::Enter | KeyCode::Down => return self.go_to_match(true),
+            KeyCode::Up => return self.go_to_match(false),
+            _ => return Ok(()),
+        }
+        // The query changed, so select the first match from where the search started
+        if let Some(search) = &mut self.search {
+            search.current = None;
+        }
+        self.go_to_match(true)
+    }
+    // Select the next or previous match, scrolling it into view
+    fn go_to_match(&mut self, forwards: bool) -> Result<()> {
+        let Some(search) = &self.search else {
+            return Ok(());
+        };
+        let Ok(matches) = search.find_matches(&self.text_data) else {
+            return Ok(());
+        };
+        if matches.is_empty() {
+            return Ok(());
+        }
+        let found = match search.current {
+            Some(current) if forwards => matches.iter().position(|m| m.start > current),
+            Some(current) => matches.iter().rposition(|m| m.start < current),
+            None => {
+                let origin = self
+                    .text_data
+                    .get_byte_index(search.origin.0, search.origin.1)
+                    .unwrap_or(0);
+                matches.iter().position(|m| m.start >= origin)
+            }
+        };
+        // Wrap around at the ends of the text
+        let index = found.unwrap_or(if forwards { 0 } else { matches.len() - 1 });
+        self.select_match(&matches[index])
+    }
+    fn select_match(&mut self, range: &Range<usize>) -> Result<()> {
+        if let Some(search) = &mut self.search {
+            // Record going to a match in the jump list, but not every match the query passes while it is typed
+            if search.current.is_some() || !search.jumped {
+                self.text_data
+                    .jumps
+                    .push((self.text_data.cursor_x, self.text_data.cursor_y));
+                search.jumped = true;
+            }
+            search.current = Some(range.start);
+        }
+        let old_cursor = (self.text_data.cursor_x, self.text_data.cursor_y);
+        let (start, end) =
+            byte_ranges_to_positions(&self.text_data.text, std::slice::from_ref(range))[0];
+        self.text_data.extra_cursors.clear();
+        self.text_data.block_selection = None;
+        self.text_data.selection_anchor = Some(start);
+        (self.text_data.cursor_x, self.text_data.cursor_y) = end;
+        self.scroll_in_view(end.0 > old_cursor.0, end.1 > old_cursor.1)
+    }
+    // Replace the selected match, then go to the next one
+    fn replace_current_match(&mut self) -> Result<()> {
+        let Some(search) = &self.search else {
+            return Ok(());
+        };
+        let Ok(matches) = search.find_matches(&self.text_data) else {
+            return Ok(());
+        };
+        let Some(range) = matches.iter().find(|m| Some(m.start) == search.current) else {
+            return self.go_to_match(true);
+        };
+        let Some(replacement) = search.expand_replacement(&self.text_data, range) else {
+            return Ok(());
+        };
+        let (start, end) =
+            byte_ranges_to_positions(&self.text_data.text, std::slice::from_ref(range))[0];
+        if !self.text_data.replace_range(start, end, &replacement)? {
+            // Skip a match that can't be replaced
+            (self.text_data.cursor_x, self.text_data.cursor_y) = end;
+        }
+        // Continue after the replacement, so it isn't matched again
+        let after = self
+            .text_data
+            .get_byte_index(self.text_data.cursor_x, self.text_data.cursor_y)
+            .unwrap_or(0);
+        if let Some(search) = &mut self.search {
+            search.current = after.checked_sub(1);
+            if search.current.is_none() {
+                search.origin = (0, 0);
+            }
+        }
+        self.go_to_match(true)
+    }
+    fn replace_all_matches(&mut self) -> Result<()> {
+        let Some(search) = &self.search else {
+            return Ok(());
+        };
+        let Ok(matches) = search.find_matches(&self.text_data) else {
+            return Ok(());
+        };
+        let Some(replacements) = matches
+            .iter()
+            .map(|range| search.expand_replacement(&self.text_data, range))
+            .collect::<Option<Vec<String>>>()
+        else {
+            return Ok(());
+        };
+        let positions = byte_ranges_to_positions(&self.text_data.text, &matches);
+        for ((start, end), replacement) in positions.iter().zip(replacements.iter()).rev() {
+            self.text_data.replace_range(*start, *end, replacement)?;
+        }
+        self.scroll_in_view(false, false)?;
+        self.scroll_in_view(true, true)
+    }
+    // Highlight the matches on the lines from `first_line` to `last_line`
+    pub(crate) fn get_search_highlights(
+        &self,
+        first_line: usize,
+        last_line: usize,
+    ) -> Vec<Highlight> {
+        let Some(search) = &self.search else {
+            return Vec::new();
+        };
+        if self.text_data.mask.is_some() {
+            return Vec::new();
+        }
+        // Only the lines on screen are searched, as this runs on every render
+        let Ok(find_in_line) = search.line_matcher() else {
+            return Vec::new();
+        };
+        let style = ContentStyle {
+            foreground_color: Some(Color::Black),
+            background_color: Some(Color::Yellow),
+            ..ContentStyle::new()
+        };
+        self.text_data
+            .text
+            .split('\n')
+            .enumerate()
+            .skip(first_line)
+            .take((last_line + 1).saturating_sub(first_line))
+            .flat_map(|(y, line)| {
+                find_in_line(line).into_iter().map(move |range| {
+                    let ((start, y), (end, _)) = char_range(y, line, range);
+                    Highlight::new(y, start, end, style)
+                })
+            })
+            .collect()
+    }
+    // Draw the search prompt at the terminal cursor
+    pub(crate) fn draw_search_prompt(&self) -> Result<()> {
+        let Some(search) = &self.search else {
+            return Ok(());
+        };
+        let status = if self.viewer.is_some() {
+            // The whole file isn't searched to count the matches
+            match (search.found, search.line_matcher()) {
+                (_, Err(error)) => Err(error),
+                (Some(((_, y), _)), _) => Ok(format!("line {}", y + 1)),
+                _ if search.not_found => Err("no matches".to_string()),
+                _ => Ok(String::new()),
+            }
+        } else if self.text_data.mask.is_some() {
+            Ok(String::new())
+        } else {
+            search.find_matches(&self.text_data).map(|matches| {
+                let current = matches
+                    .iter()
+                    .position(|m| Some(m.start) == search.current)
+                    .map_or(0, |i| i + 1);
+                format!("{current}/{}", matches.len())
+            })
+        };
+        let mut text = format!("Find: {}", search.query);
+        if let Some(replacement) = &search.replacement {
+            text += &format!("  Replace: {replacement}");
+        }
+        if search.regex {
+            text += "  (regex)";
+        }
+        print!("{text}  ");
+        let style = ContentStyle {
+            foreground_color: Some(if status.is_ok() {
+                Color::DarkGrey
+            } else {
+                Severity::Error.color()
+            }),
+            ..ContentStyle::new()
+        };
+        let status = status.unwrap_or_else(|error| error);
+        queue!(stdout(), PrintStyledContent(style.apply(status)))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultInputHandler;
+
+    fn text_data(text: &str) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(text);
+        text_data
+    }
+
+    #[test]
+    fn find_all() {
+        let text_data = text_data("abab\nä ab");
+        assert_eq!(
+            text_data.find_all("ab"),
+            vec![((0, 0), (2, 0)), ((2, 0), (4, 0)), ((2, 1), (4, 1))]
+        );
+        assert_eq!(text_data.find_all("b\nä"), vec![((3, 0), (1, 1))]);
+        assert!(text_data.find_all("").is_empty());
+    }
+
+    #[test]
+    fn replace_all() {
+        let mut text_data = text_data("one two one\none");
+        assert_eq!(text_data.replace_all("one", "1").unwrap(), 3);
+        assert_eq!(text_data.text, "1 two 1\n1");
+        assert_eq!(text_data.replace_all("missing", "x").unwrap(), 0);
+    }
+
+    #[test]
+    fn replace_range_moves_the_cursor_after_it() {
+        let mut text_data = text_data("hello world");
+        assert!(text_data.replace_range((6, 0), (11, 0), "there").unwrap());
+        assert_eq!(text_data.text, "hello there");
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (11, 0));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn replace_all_regex_with_captures() {
+        let mut text_data = text_data("x=1, y=22");
+        let regex = regex::Regex::new(r"(?P<name>\w)=(\d+)").unwrap();
+        assert_eq!(
+            text_data.replace_all_regex(&regex, "$2=${name}").unwrap(),
+            2
+        );
+        assert_eq!(text_data.text, "1=x, 22=y");
+    }
+
+    #[test]
+    fn line_matcher_finds_matches_in_a_line() {
+        let search = Search {
+            query: "a".to_string(),
+            ..Search::default()
+        };
+        let find_in_line = search.line_matcher().unwrap();
+        assert_eq!(find_in_line("banana"), vec![1..2, 3..4, 5..6]);
+    }
+
+    #[test]
+    fn replace_all_without_replacement_asks_for_one() {
+        let mut input = CoolInput::new(DefaultInputHandler, 4);
+        input.text_data.load_text("a a");
+        input.open_search();
+        input
+            .handle_search_key_press(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE))
+            .unwrap();
+        input
+            .handle_search_key_press(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::ALT))
+            .unwrap();
+        assert_eq!(input.text_data.text, "a a");
+        let search = input.search.as_ref().unwrap();
+        assert!(search.editing_replacement);
+        assert_eq!(search.replacement.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn highlights_only_visible_lines() {
+        let mut input = CoolInput::new(DefaultInputHandler, 4);
+        input.text_data.load_text("ab\nb\nbb\nb");
+        input.search = Some(Search {
+            query: "b".to_string(),
+            ..Search::default()
+        });
+        let highlights = input.get_search_highlights(1, 2);
+        let lines: Vec<usize> = highlights.iter().map(|highlight| highlight.line).collect();
+        assert_eq!(lines, vec![1, 2, 2]);
+    }
+
+    #[test]
+    fn masked_text_isnt_searched() {
+        let mut input = CoolInput::new(DefaultInputHandler, 4);
+        input.text_data.load_text("secret");
+        input.text_data.mask = Some(crate::Mask::Hidden);
+        input.open_search();
+        assert!(input.search.is_none());
+    }
+}
@@ -0,0 +1,482 @@
+use crate::{
+    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputOutcome, InputTransform,
+    KeyPressResult, TextInputData,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use crossterm::{cursor, queue};
+use std::cmp;
+use std::fmt::Display;
+use std::io::{stdout, Result};
+
+// Keys shared by all list prompts
+enum ListKey {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Submit,
+    Cancel,
+    Other(KeyCode),
+}
+
+fn get_list_key(key: &Event) -> Option<ListKey> {
+    if let Event::Key(key_event) = key {
+        if key_event.kind == KeyEventKind::Press {
+            return Some(match key_event.code {
+                KeyCode::Up => ListKey::Up,
+                KeyCode::Down => ListKey::Down,
+                KeyCode::PageUp => ListKey::PageUp,
+                KeyCode::PageDown => ListKey::PageDown,
+                KeyCode::Home => ListKey::Home,
+                KeyCode::End => ListKey::End,
+                KeyCode::Enter => ListKey::Submit,
+                KeyCode::Esc => ListKey::Cancel,
+                KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    ListKey::Cancel
+                }
+                code => ListKey::Other(code),
+            });
+        }
+    }
+    None
+}
+
+/// State of a scrollable list of items, with one item under the cursor
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ListState {
+    pub cursor: usize,
+    pub scroll: usize,
+}
+
+impl ListState {
+    /// Move the cursor for a key press. Returns false if the key doesn't move the cursor.
+    fn handle_key(&mut self, key: &ListKey, amt_items: usize, height: usize) -> bool {
+        if amt_items == 0 {
+            return false;
+        }
+        let last = amt_items - 1;
+        self.cursor = match key {
+            ListKey::Up if self.cursor == 0 => last,
+            ListKey::Up => self.cursor - 1,
+            ListKey::Down if self.cursor >= last => 0,
+            ListKey::Down => self.cursor + 1,
+            ListKey::PageUp => self.cursor.saturating_sub(height),
+            ListKey::PageDown => cmp::min(self.cursor + height, last),
+            ListKey::Home => 0,
+            ListKey::End => last,
+            _ => return false,
+        };
+        true
+    }
+    /// Scroll so that the cursor is within the visible rows
+    pub fn scroll_to_cursor(&mut self, height: usize) {
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if height > 0 && self.cursor >= self.scroll + height {
+            self.scroll = self.cursor + 1 - height;
+        }
+    }
+}
+
+/// Draw a list of rows in an area, scrolled by `state`. The row under the cursor is highlighted.
+pub(crate) fn draw_list(
+    rows: &[String],
+    state: &ListState,
+    transform: &InputTransform,
+    highlight_cursor: bool,
+) {
+    let width = transform.size.0 as usize;
+    for i in 0..transform.size.1 as usize {
+        let y = transform.offset.1 as usize + i;
+        let index = state.scroll + i;
+        let _ = set_terminal_line("", transform.offset.0 as usize, y, true);
+        if let Some(row) = rows.get(index) {
+            let row: String = row.chars().take(width).collect();
+            if highlight_cursor && index == state.cursor {
+                let _ = queue!(stdout(), SetForegroundColor(Color::Cyan));
+            }
+            let _ = set_terminal_line(&row, transform.offset.0 as usize, y, false);
+            let _ = queue!(stdout(), ResetColor);
+        }
+    }
+}
+
+// The area of a list below its prompt, at most `max_height` rows
+fn list_transform(
+    terminal_size: &(u16, u16),
+    amt_items: usize,
+    max_height: Option<u16>,
+) -> InputTransform {
+    let mut height = cmp::min(terminal_size.1.saturating_sub(1), amt_items as u16);
+    if let Some(max_height) = max_height {
+        height = cmp::min(height, max_height);
+    }
+    InputTransform {
+        size: (terminal_size.0, cmp::max(height, 1)),
+        offset: (0, 1),
+    }
+}
+
+/// A prompt to pick one item from a list with the arrow keys. Lists longer than the screen are scrolled.
+pub struct Select<T: Display> {
+    pub prompt: String,
+    pub items: Vec<T>,
+    /// Maximum amount of rows the list takes up
+    pub max_height: Option<u16>,
+    state: ListState,
+}
+
+impl<T: Display> Select<T> {
+    pub fn new(prompt: &str, items: Vec<T>) -> Self {
+        Select {
+            prompt: prompt.to_string(),
+            items,
+            max_height: None,
+            state: ListState::default(),
+        }
+    }
+    /// Set the item that is selected at first
+    pub fn default_index(mut self, index: usize) -> Self {
+        self.state.cursor = cmp::min(index, self.items.len().saturating_sub(1));
+        self
+    }
+    /// Set the maximum amount of rows the list takes up
+    pub fn max_height(mut self, max_height: u16) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+    /// Get the index of the selected item
+    pub fn selected_index(&self) -> usize {
+        self.state.cursor
+    }
+    /// Prepare the terminal and show the prompt. Returns the picked item, or `None` if the prompt was cancelled.
+    pub fn ask(self) -> Result<Option<T>> {
+        let mut input = CoolInput::new(self, 0);
+        input.status_on_last_row = true;
+        match input.listen()? {
+            InputOutcome::Submitted(_) => {
+                let index = input.custom_input.state.cursor;
+                Ok(Some(input.custom_input.items.swap_remove(index)))
+            }
+            InputOutcome::Cancelled => Ok(None),
+        }
+    }
+}
+
+impl<T: Display> CustomInputHandler for Select<T> {
+    fn handle_key_press(&mut self, key: &Event, ctx: HandlerContext) -> KeyPressResult {
+        let height = self.get_input_transform(ctx).size.1 as usize;
+        match get_list_key(key) {
+            Some(ListKey::Submit) => KeyPressResult::Submit,
+            Some(ListKey::Cancel) => KeyPressResult::Stop,
+            Some(key) => {
+                if self.state.handle_key(&key, self.items.len(), height) {
+                    self.state.scroll_to_cursor(height);
+                }
+                KeyPressResult::Handled
+            }
+            None => KeyPressResult::Handled,
+        }
+    }
+    fn after_draw_text(&mut self, ctx: HandlerContext) {
+        let _ = set_terminal_line(&self.prompt, 0, 0, true);
+        let transform = self.get_input_transform(ctx);
+        let rows: Vec<String> = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let marker = if i == self.state.cursor { '>' } else { ' ' };
+                format!("{marker} {item}")
+            })
+            .collect();
+        draw_list(&rows, &self.state, &transform, true);
+    }
+    fn after_update_cursor(&mut self, _: HandlerContext) {
+        let _ = queue!(stdout(), cursor::Hide);
+    }
+    fn validate(&mut self, _: &TextInputData) -> std::result::Result<(), String> {
+        if self.items.is_empty() {
+            return Err("there is nothing to select".to_string());
+        }
+        Ok(())
+    }
+    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
+        list_transform(ctx.terminal_size, self.items.len(), self.max_height)
+    }
+}
+
+/// A prompt to pick any amount of items from a list. Space toggles the item under the cursor, and `a` toggles all items.
+pub struct MultiSelect<T: Display> {
+    pub prompt: String,
+    pub items: Vec<T>,
+    /// Whether each item is checked
+    pub checked: Vec<bool>,
+    /// Maximum amount of rows the list takes up
+    pub max_height: Option<u16>,
+    /// Least amount of items that have to be checked to submit
+    pub min_selected: usize,
+    state: ListState,
+}
+
+impl<T: Display> MultiSelect<T> {
+    pub fn new(prompt: &str, items: Vec<T>) -> Self {
+        let checked = vec![false; items.len()];
+        MultiSelect {
+            prompt: prompt.to_string(),
+            items,
+            checked,
+            max_height: None,
+            min_selected: 0,
+            state: ListState::default(),
+        }
+    }
+    /// Set which items are checked at first
+    pub fn defaults(mut self, checked: &[usize]) -> Self {
+        for index in checked {
+            if let Some(value) = self.checked.get_mut(*index) {
+                *value = true;
+            }
+        }
+        self
+    }
+    /// Set the maximum amount of rows the list takes up
+    pub fn max_height(mut self, max_height: u16) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+    /// Set the least amount of items that have to be checked to submit
+    pub fn min_selected(mut self, min_selected: usize) -> Self {
+        self.min_selected = min_selected;
+        self
+    }
+    /// Prepare the terminal and show the prompt. Returns the checked items, or `None` if the prompt was cancelled.
+    pub fn ask(self) -> Result<Option<Vec<T>>> {
+        let mut input = CoolInput::new(self, 0);
+        input.status_on_last_row = true;
+        match input.listen()? {
+            InputOutcome::Submitted(_) => {
+                let handler = input.custom_input;
+                let items = handler
+                    .items
+                    .into_iter()
+                    .zip(handler.checked)
+                    .filter(|(_, checked)| *checked)
+                    .map(|(item, _)| item)
+                    .collect();
+                Ok(Some(items))
+            }
+            InputOutcome::Cancelled => Ok(None),
+        }
+    }
+}
+
+impl<T: Display> CustomInputHandler for MultiSelect<T> {
+    fn handle_key_press(&mut self, key: &Event, ctx: HandlerContext) -> KeyPressResult {
+        let height = self.get_input_transform(ctx).size.1 as usize;
+        match get_list_key(key) {
+            Some(ListKey::Submit) => KeyPressResult::Submit,
+            Some(ListKey::Cancel) => KeyPressResult::Stop,
+            Some(ListKey::Other(KeyCode::Char(' '))) => {
+                if let Some(checked) = self.checked.get_mut(self.state.cursor) {
+                    *checked = !*checked;
+                }
+                KeyPressResult::Handled
+            }
+            Some(ListKey::Other(KeyCode::Char('a'))) => {
+                let all_checked = self.checked.iter().all(|checked| *checked);
+                self.checked
+                    .iter_mut()
+                    .for_each(|checked| *checked = !all_checked);
+                KeyPressResult::Handled
+            }
+            Some(key) => {
+                if self.state.handle_key(&key, self.items.len(), height) {
+                    self.state.scroll_to_cursor(height);
+                }
+                KeyPressResult::Handled
+            }
+            None => KeyPressResult::Handled,
+        }
+    }
+    fn after_draw_text(&mut self, ctx: HandlerContext) {
+        let _ = set_terminal_line(&self.prompt, 0, 0, true);
+        let transform = self.get_input_transform(ctx);
+        let rows: Vec<String> = self
+            .items
+            .iter()
+            .zip(&self.checked)
+            .enumerate()
+            .map(|(i, (item, checked))| {
+                let marker = if i == self.state.cursor { '>' } else { ' ' };
+                let check = if *checked { 'x' } else { ' ' };
+                format!("{marker} [{check}] {item}")
+            })
+            .collect();
+        draw_list(&rows, &self.state, &transform, true);
+    }
+    fn after_update_cursor(&mut self, _: HandlerContext) {
+        let _ = queue!(stdout(), cursor::Hide);
+    }
+    fn validate(&mut self, _: &TextInputData) -> std::result::Result<(), String> {
+        let amt_checked = self.checked.iter().filter(|checked| **checked).count();
+        if amt_checked < self.min_selected {
+            return Err(format!("select at least {}", self.min_selected));
+        }
+        Ok(())
+    }
+    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
+        list_transform(ctx.terminal_size, self.items.len(), self.max_height)
+    }
+}
+
+/// A yes/no prompt. Stops on the first `y` or `n` press, and Enter picks the default answer.
+pub struct Confirm {
+    pub prompt: String,
+    pub default: bool,
+    pub value: bool,
+}
+
+impl Confirm {
+    pub fn new(prompt: &str, default: bool) -> Self {
+        Confirm {
+            prompt: prompt.to_string(),
+            default,
+            value: default,
+        }
+    }
+    /// Prepare the terminal and show the prompt. Returns the answer, or `None` if the prompt was cancelled.
+    pub fn ask(self) -> Result<Option<bool>> {
+        let mut input = CoolInput::new(self, 0);
+        input.status_on_last_row = true;
+        match input.listen()? {
+            InputOutcome::Submitted(_) => Ok(Some(input.custom_input.value)),
+            InputOutcome::Cancelled => Ok(None),
+        }
+    }
+}
+
+impl CustomInputHandler for Confirm {
+    fn handle_key_press(&mut self, key: &Event, _: HandlerContext) -> KeyPressResult {
+        match get_list_key(key) {
+            Some(ListKey::Submit) => {
+                self.value = self.default;
+                KeyPressResult::Submit
+            }
+            Some(ListKey::Cancel) => KeyPressResult::Stop,
+            Some(ListKey::Other(KeyCode::Char(c))) if c.eq_ignore_ascii_case(&'y') => {
+                self.value = true;
+                KeyPressResult::Submit
+            }
+            Some(ListKey::Other(KeyCode::Char(c))) if c.eq_ignore_ascii_case(&'n') => {
+                self.value = false;
+                KeyPressResult::Submit
+            }
+            _ => KeyPressResult::Handled,
+        }
+    }
+    fn after_draw_text(&mut self, _: HandlerContext) {
+        let choices = if self.default { "[Y/n]" } else { "[y/N]" };
+        let _ = set_terminal_line(&format!("{} {choices}", self.prompt), 0, 0, true);
+    }
+    fn after_update_cursor(&mut self, _: HandlerContext) {
+        let _ = queue!(stdout(), cursor::Hide);
+    }
+    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
+        InputTransform {
+            size: (ctx.terminal_size.0, 1),
+            offset: (0, 1),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyEvent;
+
+    // Press a key in a handler, with a terminal of 80x10
+    fn press<H: CustomInputHandler>(handler: &mut H, code: KeyCode) -> KeyPressResult {
+        let mut text_data = TextInputData::new(0);
+        let key = Event::Key(KeyEvent::new(code, KeyModifiers::NONE));
+        handler.handle_key_press(
+            &key,
+            HandlerContext {
+                text_data: &mut text_data,
+                terminal_size: &(80, 10),
+            },
+        )
+    }
+
+    #[test]
+    fn list_keys_wrap_and_scroll() {
+        let mut state = ListState::default();
+        assert!(state.handle_key(&ListKey::Up, 20, 5));
+        assert_eq!(state.cursor, 19);
+        state.scroll_to_cursor(5);
+        assert_eq!(state.scroll, 15);
+        assert!(state.handle_key(&ListKey::Down, 20, 5));
+        assert_eq!(state.cursor, 0);
+        state.scroll_to_cursor(5);
+        assert_eq!(state.scroll, 0);
+        assert!(state.handle_key(&ListKey::PageDown, 20, 5));
+        assert_eq!(state.cursor, 5);
+        assert!(!state.handle_key(&ListKey::Other(KeyCode::Char('x')), 20, 5));
+        assert!(!state.handle_key(&ListKey::Down, 0, 5));
+    }
+
+    #[test]
+    fn select_moves_within_the_screen() {
+        let items: Vec<usize> = (0..30).collect();
+        let mut select = Select::new("Pick", items).default_index(50);
+        assert_eq!(select.selected_index(), 29);
+        press(&mut select, KeyCode::Home);
+        // The list has the 9 rows below the prompt
+        press(&mut select, KeyCode::PageDown);
+        assert_eq!(select.selected_index(), 9);
+        assert_eq!(select.state.scroll, 1);
+        assert!(matches!(
+            press(&mut select, KeyCode::Enter),
+            KeyPressResult::Submit
+        ));
+        assert!(matches!(
+            press(&mut select, KeyCode::Esc),
+            KeyPressResult::Stop
+        ));
+    }
+
+    #[test]
+    fn multi_select_toggles() {
+        let mut select = MultiSelect::new("Pick", vec!["a", "b", "c"])
+            .defaults(&[1, 5])
+            .min_selected(2);
+        assert_eq!(select.checked, vec![false, true, false]);
+        assert!(select.validate(&TextInputData::new(0)).is_err());
+        press(&mut select, KeyCode::Char(' '));
+        assert_eq!(select.checked, vec![true, true, false]);
+        assert!(select.validate(&TextInputData::new(0)).is_ok());
+        press(&mut select, KeyCode::Char('a'));
+        assert_eq!(select.checked, vec![true, true, true]);
+        press(&mut select, KeyCode::Char('a'));
+        assert_eq!(select.checked, vec![false, false, false]);
+    }
+
+    #[test]
+    fn confirm_answers() {
+        let mut confirm = Confirm::new("Sure?", true);
+        assert!(matches!(
+            press(&mut confirm, KeyCode::Char('N')),
+            KeyPressResult::Submit
+        ));
+        assert!(!confirm.value);
+        press(&mut confirm, KeyCode::Enter);
+        assert!(confirm.value);
+        assert!(matches!(
+            press(&mut confirm, KeyCode::Char('x')),
+            KeyPressResult::Handled
+        ));
+    }
+}
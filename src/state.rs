@@ -0,0 +1,184 @@
+use crate::{
+    Annotation, BlockSelection, CoolInput, Cursor, CustomInputHandler, JumpList, LineEnding,
+    TextInputData, TextRange, UndoHistory,
+};
+use std::cmp;
+use std::collections::HashMap;
+
+/// A snapshot of the session of a [CoolInput]: the text, where the cursors and selections are, how far it is scrolled, the marks and jumps, and the undo history.
+///
+/// Taken with [get_state](CoolInput::get_state) and restored with [restore_state](CoolInput::restore_state).
+/// With the `serde` feature it can be serialized, ex. to reopen a draft where it was left.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoolInputState {
+    pub text: String,
+    pub line_ending: LineEnding,
+    /// Position (x, y) of the main cursor
+    pub cursor: (usize, usize),
+    pub selection_anchor: Option<(usize, usize)>,
+    pub extra_cursors: Vec<Cursor>,
+    pub block_selection: Option<BlockSelection>,
+    pub scroll_x: usize,
+    pub scroll_y: usize,
+    pub marks: HashMap<char, (usize, usize)>,
+    pub jumps: JumpList,
+    pub annotations: Vec<Annotation>,
+    pub protected_ranges: Vec<TextRange>,
+    pub undo_history: UndoHistory,
+}
+
+impl TextInputData {
+    /// Move every position that is outside the text back inside it, ex. after the text was set directly or deserialized.
+    /// Block selections keep their columns, as they may go past the end of lines.
+    pub fn clamp_positions(&mut self) {
+        let line_lengths: Vec<usize> = self.text.split('\n').map(|l| l.chars().count()).collect();
+        let last_line = line_lengths.len() - 1;
+        let clamp = |position: &mut (usize, usize)| {
+            position.1 = cmp::min(position.1, last_line);
+            position.0 = cmp::min(position.0, line_lengths[position.1]);
+        };
+
+        let mut cursor = (self.cursor_x, self.cursor_y);
+        clamp(&mut cursor);
+        (self.cursor_x, self.cursor_y) = cursor;
+        self.selection_anchor.iter_mut().for_each(clamp);
+        for extra_cursor in self.extra_cursors.iter_mut() {
+            let mut position = (extra_cursor.x, extra_cursor.y);
+            clamp(&mut position);
+            (extra_cursor.x, extra_cursor.y) = position;
+            extra_cursor.selection_anchor.iter_mut().for_each(clamp);
+        }
+        if let Some(block) = &mut self.block_selection {
+            block.anchor.1 = cmp::min(block.anchor.1, last_line);
+            block.cursor.1 = cmp::min(block.cursor.1, last_line);
+        }
+        self.marks.values_mut().for_each(clamp);
+        self.jumps.positions_mut().for_each(clamp);
+        for annotation in self.annotations.iter_mut() {
+            clamp(&mut annotation.start);
+            clamp(&mut annotation.end);
+        }
+        for (start, end) in self.protected_ranges.iter_mut() {
+            clamp(start);
+            clamp(end);
+        }
+        // Cursors that were moved onto each other become one
+        self.merge_cursors();
+    }
+}
+
+impl<H: CustomInputHandler> CoolInput<H> {
+    /// Take a [snapshot](CoolInputState) of the session. While a [mask](TextInputData::mask) is set the text and undo history are left out, so a password isn't saved along with it.
+    pub fn get_state(&self) -> CoolInputState {
+        let text_data = &self.text_data;
+        let (text, undo_history) = match text_data.mask {
+            Some(_) => (String::new(), UndoHistory::default()),
+            None => (text_data.text.clone(), text_data.undo_history.clone()),
+        };
+        CoolInputState {
+            text,
+            line_ending: text_data.line_ending,
+            cursor: (text_data.cursor_x, text_data.cursor_y),
+            selection_anchor: text_data.selection_anchor,
+            extra_cursors: text_data.extra_cursors.clone(),
+            block_selection: text_data.block_selection,
+            scroll_x: self.scroll_x,
+            scroll_y: self.scroll_y,
+            marks: text_data.marks.clone(),
+            jumps: text_data.jumps.clone(),
+            annotations: text_data.annotations.clone(),
+            protected_ranges: text_data.protected_ranges.clone(),
+            undo_history,
+        }
+    }
+    /// Restore a [snapshot](CoolInputState) of a session. Positions that are outside the text, ex. because the snapshot was edited, are moved inside it.
+    ///
+    /// Restoring isn't an edit: it isn't passed to [on_change](CustomInputHandler::on_change) and can't be undone, though the [revision](TextInputData::revision) goes up.
+    pub fn restore_state(&mut self, state: CoolInputState) {
+        let text_data = &mut self.text_data;
+        text_data.text = LineEnding::normalize(&state.text);
+        text_data.revision += 1;
+        // Edits not yet taken belong to the text before
+        text_data.pending_edits.clear();
+        text_data.finished_edits.clear();
+        text_data.undo_history = state.undo_history;
+        text_data.line_ending = state.line_ending;
+        text_data.mixed_line_endings = false;
+        (text_data.cursor_x, text_data.cursor_y) = state.cursor;
+        text_data.selection_anchor = state.selection_anchor;
+        text_data.extra_cursors = state.extra_cursors;
+        text_data.block_selection = state.block_selection;
+        text_data.marks = state.marks;
+        text_data.jumps = state.jumps;
+        text_data.annotations = state.annotations;
+        text_data.protected_ranges = state.protected_ranges;
+        text_data.clamp_positions();
+        self.scroll_x = state.scroll_x;
+        self.scroll_y = cmp::min(
+            state.scroll_y,
+            self.text_data.get_amt_lines().saturating_sub(1),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefaultInputHandler, Mask};
+
+    fn input(text: &str) -> CoolInput<DefaultInputHandler> {
+        let mut input = CoolInput::new(DefaultInputHandler, 4);
+        input.text_data.load_text(text);
+        input.text_data.take_edits();
+        input
+    }
+
+    #[test]
+    fn restore_a_snapshot() {
+        let mut input = input("one\ntwo");
+        (input.text_data.cursor_x, input.text_data.cursor_y) = (2, 1);
+        input.text_data.selection_anchor = Some((0, 1));
+        input.text_data.write_char('x').unwrap();
+        input.text_data.take_edits();
+        input.text_data.set_mark('a');
+        let state = input.get_state();
+
+        let mut restored = self::input("something else");
+        let revision = restored.text_data.revision();
+        restored.restore_state(state.clone());
+        assert_eq!(restored.get_state(), state);
+        assert!(restored.text_data.revision() > revision);
+        // Restoring isn't an edit, but what was restored can be undone
+        assert!(restored.text_data.take_edits().is_empty());
+        restored.text_data.undo().unwrap();
+        assert_eq!(restored.text_data.text, "one\ntwo");
+    }
+
+    #[test]
+    fn positions_are_moved_inside_the_text() {
+        let mut state = input("ab\ncd").get_state();
+        state.cursor = (10, 10);
+        state.selection_anchor = Some((5, 0));
+        state.marks.insert('a', (3, 7));
+        state.scroll_y = 20;
+        let mut input = input("");
+        input.restore_state(state);
+        let text_data = &input.text_data;
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (2, 1));
+        assert_eq!(text_data.selection_anchor, Some((2, 0)));
+        assert_eq!(text_data.marks[&'a'], (2, 1));
+        assert_eq!(input.scroll_y, 1);
+    }
+
+    #[test]
+    fn masked_text_is_left_out() {
+        let mut input = input("");
+        input.text_data.write_str("secret").unwrap();
+        input.text_data.take_edits();
+        input.text_data.mask = Some(Mask::Hidden);
+        let state = input.get_state();
+        assert_eq!(state.text, "");
+        assert!(!state.undo_history.can_undo());
+    }
+}
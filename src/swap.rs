@@ -0,0 +1,158 @@
+use crate::document::create_private_file;
+use crate::{Document, TextInputData};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How often a [SwapFile] is written by default
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A copy of unsaved text kept next to a document, so edits can be recovered if the program is killed before saving.
+///
+/// Call [autosave](SwapFile::autosave) regularly, ex. from [on_tick](crate::CustomInputHandler::on_tick),
+/// and [remove](SwapFile::remove) the swap file when the program exits normally.
+/// If it still exists when the document is opened again, the last run didn't exit normally and its text can be [recovered](SwapFile::recover).
+#[derive(Clone, Debug)]
+pub struct SwapFile {
+    pub path: PathBuf,
+    /// Minimum time between writes of the swap file
+    pub interval: Duration,
+    /// Revision of the text that was last written, or `None` if nothing was written
+    written_revision: Option<u64>,
+    last_write: Option<Instant>,
+}
+
+impl SwapFile {
+    /// Get the swap file of a document at a path, named `.<file name>.swp` in the same directory
+    pub fn for_path(document_path: impl AsRef<Path>) -> Self {
+        let document_path = document_path.as_ref();
+        let file_name = document_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        SwapFile {
+            path: document_path.with_file_name(format!(".{file_name}.swp")),
+            interval: DEFAULT_INTERVAL,
+            written_revision: None,
+            last_write: None,
+        }
+    }
+    /// Get the swap file of a [Document], if it has a path
+    pub fn for_document(document: &Document) -> Option<Self> {
+        document.path.as_ref().map(SwapFile::for_path)
+    }
+    /// Whether a swap file was left behind by a run that didn't exit normally
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+    /// Read the text kept in the swap file into a [TextInputData]
+    pub fn recover(&self, text_data: &mut TextInputData) -> io::Result<()> {
+        text_data.load_text(&fs::read_to_string(&self.path)?);
+        Ok(())
+    }
+    /// Write the text to the swap file, if it changed since it was last written and [interval](SwapFile::interval) has passed.
+    /// Once the document is saved, the swap file is removed instead. Returns whether the swap file was written.
+    pub fn autosave(&mut self, document: &Document, text_data: &TextInputData) -> io::Result<bool> {
+        if !document.is_dirty(text_data) {
+            if self.written_revision.is_some() {
+                self.remove()?;
+            }
+            return Ok(false);
+        }
+        if self.written_revision == Some(text_data.revision()) {
+            return Ok(false);
+        }
+        if self
+            .last_write
+            .is_some_and(|last_write| last_write.elapsed() < self.interval)
+        {
+            return Ok(false);
+        }
+        self.write(text_data)?;
+        Ok(true)
+    }
+    /// Write the text to the swap file now. On Unix, the swap file can only be read by the current user.
+    pub fn write(&mut self, text_data: &TextInputData) -> io::Result<()> {
+        // Write next to the swap file first, so a crash while writing doesn't destroy the last good copy
+        let mut temp_path = self.path.clone().into_os_string();
+        temp_path.push(".tmp");
+        // Only readable by the current user, as the document may be private. A leftover file would keep its permissions.
+        let _ = fs::remove_file(&temp_path);
+        let mut file = create_private_file(Path::new(&temp_path))?;
+        file.write_all(text_data.get_text_with_line_endings().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, &self.path)?;
+        self.written_revision = Some(text_data.revision());
+        self.last_write = Some(Instant::now());
+        Ok(())
+    }
+    /// Delete the swap file, ex. after saving the document or on a normal exit
+    pub fn remove(&mut self) -> io::Result<()> {
+        self.written_revision = None;
+        match fs::remove_file(&self.path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn swap_file_path() {
+        let swap_file = SwapFile::for_path("dir/notes.txt");
+        assert_eq!(swap_file.path, Path::new("dir/.notes.txt.swp"));
+        assert!(SwapFile::for_document(&Document::default()).is_none());
+    }
+
+    #[test]
+    fn autosave_and_recover() {
+        let path = std::env::temp_dir().join(format!("cool-rust-input-{}-swap.txt", process::id()));
+        let mut text_data = TextInputData::new(4);
+        let mut document = Document::new(&path);
+        document.save(&text_data).unwrap();
+        let mut swap_file = SwapFile::for_document(&document).unwrap();
+        swap_file.interval = Duration::ZERO;
+
+        // Nothing to keep while the document is saved
+        assert!(!swap_file.autosave(&document, &text_data).unwrap());
+        assert!(!swap_file.exists());
+
+        text_data.write_str("unsaved").unwrap();
+        assert!(swap_file.autosave(&document, &text_data).unwrap());
+        assert!(swap_file.exists());
+        // Not written again until the text changes
+        assert!(!swap_file.autosave(&document, &text_data).unwrap());
+
+        let mut recovered = TextInputData::new(4);
+        swap_file.recover(&mut recovered).unwrap();
+        assert_eq!(recovered.text, "unsaved");
+
+        // Saving the document removes the swap file
+        document.save(&text_data).unwrap();
+        assert!(!swap_file.autosave(&document, &text_data).unwrap());
+        assert!(!swap_file.exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn autosave_waits_for_interval() {
+        let path =
+            std::env::temp_dir().join(format!("cool-rust-input-{}-interval.txt", process::id()));
+        let mut text_data = TextInputData::new(4);
+        let document = Document::new(&path);
+        let mut swap_file = SwapFile::for_document(&document).unwrap();
+        swap_file.interval = Duration::from_secs(3600);
+
+        text_data.write_char('a').unwrap();
+        assert!(swap_file.autosave(&document, &text_data).unwrap());
+        text_data.write_char('b').unwrap();
+        assert!(!swap_file.autosave(&document, &text_data).unwrap());
+        swap_file.remove().unwrap();
+        assert!(!swap_file.exists());
+    }
+}
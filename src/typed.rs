@@ -0,0 +1,507 @@
+use crate::{
+    set_terminal_line, CoolInput, CustomInputHandler, HandlerContext, InputConstraints,
+    InputOutcome, InputTransform, KeyPressResult, Severity, TextInputData,
+};
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::{ResetColor, SetForegroundColor};
+use std::fmt::{self, Display};
+use std::io::{stdout, Result};
+use std::str::FromStr;
+
+/// Parses the text of a [TypedInput] into a value
+#[allow(unused_variables)]
+pub trait InputParser {
+    type Output;
+    /// Whether the character may be typed into the input
+    fn allows_char(&self, c: char) -> bool {
+        true
+    }
+    /// Parse the text, or get a message explaining why it's invalid
+    fn parse(&self, text: &str) -> std::result::Result<Self::Output, String>;
+    /// Called when Up (`up` is true) or Down is pressed. Returns the new text of the input, if the value can be stepped.
+    fn step(&self, text: &str, up: bool) -> Option<String> {
+        None
+    }
+}
+
+/// A single line prompt that only accepts text that can be parsed to a value.
+///
+/// Keystrokes are restricted to the characters the [parser](InputParser) allows, and parse errors are shown live below the input.
+pub struct TypedInput<P: InputParser> {
+    pub prompt: String,
+    pub parser: P,
+    // Whether the status row shows the error, because submitting failed
+    submit_failed: bool,
+}
+
+impl<P: InputParser + Clone + 'static> TypedInput<P> {
+    pub fn with_parser(prompt: &str, parser: P) -> Self {
+        TypedInput {
+            prompt: prompt.to_string(),
+            parser,
+            submit_failed: false,
+        }
+    }
+    /// Prepare the terminal and show the prompt. Returns the parsed value, or `None` if the prompt was cancelled.
+    pub fn ask(self) -> Result<Option<P::Output>> {
+        let filter_parser = self.parser.clone();
+        let mut input = CoolInput::new(self, 0);
+        // Errors are shown below the input, and the row is cleared on every render
+        input.status_row = Some(1);
+        input.text_data.constraints =
+            InputConstraints::single_line().char_filter(move |c| filter_parser.allows_char(c));
+        match input.listen()? {
+            InputOutcome::Submitted(text) => Ok(input.custom_input.parser.parse(&text).ok()),
+            InputOutcome::Cancelled => Ok(None),
+        }
+    }
+}
+
+impl<P: InputParser> CustomInputHandler for TypedInput<P> {
+    fn handle_key_press(&mut self, key: &Event, ctx: HandlerContext) -> KeyPressResult {
+        if let Event::Key(key_event) = key {
+            if key_event.kind == KeyEventKind::Press {
+                self.submit_failed = false;
+                match key_event.code {
+                    KeyCode::Enter => return KeyPressResult::Submit,
+                    KeyCode::Esc => return KeyPressResult::Stop,
+                    KeyCode::Char('c') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        return KeyPressResult::Stop;
+                    }
+                    KeyCode::Up | KeyCode::Down => {
+                        let up = key_event.code == KeyCode::Up;
+                        if let Some(text) = self.parser.step(&ctx.text_data.text, up) {
+                            let _ = ctx.text_data.replace_text(&text);
+                        }
+                        return KeyPressResult::Handled;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        KeyPressResult::Continue
+    }
+    fn after_draw_text(&mut self, ctx: HandlerContext) {
+        let _ = queue!(stdout(), ResetColor);
+        let _ = set_terminal_line(&self.prompt, 0, 0, false);
+        // After a failed submit the status row already shows the error
+        if ctx.text_data.text.is_empty() || self.submit_failed {
+            return;
+        }
+        if let Err(error) = self.parser.parse(&ctx.text_data.text) {
+            let _ = queue!(stdout(), SetForegroundColor(Severity::Error.color()));
+            let _ = set_terminal_line(&error, 0, 1, false);
+            let _ = queue!(stdout(), ResetColor);
+        }
+    }
+    fn validate(&mut self, text_data: &TextInputData) -> std::result::Result<(), String> {
+        let result = self.parser.parse(&text_data.text).map(|_| ());
+        self.submit_failed = result.is_err();
+        result
+    }
+    fn get_input_transform(&mut self, ctx: HandlerContext) -> InputTransform {
+        let prompt_offset = self.prompt.chars().count() as u16 + 1;
+        InputTransform {
+            size: (ctx.terminal_size.0.saturating_sub(prompt_offset), 1),
+            offset: (prompt_offset, 0),
+        }
+    }
+}
+
+/// A number type that can be used with a [NumberInput]
+pub trait Number: FromStr + Display + Copy + PartialOrd {
+    const IS_FLOAT: bool;
+    const IS_SIGNED: bool;
+    fn zero() -> Self;
+    fn one() -> Self;
+    /// Add (or subtract if `up` is false) `step`, without overflowing
+    fn step_by(self, step: Self, up: bool) -> Self;
+}
+
+macro_rules! impl_number_int {
+    ($signed:expr, $($t:ty),*) => {$(
+        impl Number for $t {
+            const IS_FLOAT: bool = false;
+            const IS_SIGNED: bool = $signed;
+            fn zero() -> Self {
+                0
+            }
+            fn one() -> Self {
+                1
+            }
+            fn step_by(self, step: Self, up: bool) -> Self {
+                if up {
+                    self.saturating_add(step)
+                } else {
+                    self.saturating_sub(step)
+                }
+            }
+        }
+    )*};
+}
+impl_number_int!(true, i8, i16, i32, i64, i128, isize);
+impl_number_int!(false, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_number_float {
+    ($($t:ty),*) => {$(
+        impl Number for $t {
+            const IS_FLOAT: bool = true;
+            const IS_SIGNED: bool = true;
+            fn zero() -> Self {
+                0.0
+            }
+            fn one() -> Self {
+                1.0
+            }
+            fn step_by(self, step: Self, up: bool) -> Self {
+                if up {
+                    self + step
+                } else {
+                    self - step
+                }
+            }
+        }
+    )*};
+}
+impl_number_float!(f32, f64);
+
+/// [Parser](InputParser) for numbers, with an optional range. Up and Down step the number.
+#[derive(Clone, Copy, Debug)]
+pub struct NumberParser<T: Number> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+    /// Amount the number changes by when Up or Down is pressed
+    pub step: T,
+}
+
+impl<T: Number> InputParser for NumberParser<T> {
+    type Output = T;
+    fn allows_char(&self, c: char) -> bool {
+        c.is_ascii_digit()
+            || (c == '-' && T::IS_SIGNED)
+            || (matches!(c, '.' | 'e' | 'E' | '+') && T::IS_FLOAT)
+    }
+    fn parse(&self, text: &str) -> std::result::Result<T, String> {
+        let value: T = text
+            .parse()
+            .map_err(|_| format!("'{text}' is not a valid number"))?;
+        if let Some(min) = self.min {
+            if value < min {
+                return Err(format!("must be at least {min}"));
+            }
+        }
+        if let Some(max) = self.max {
+            if value > max {
+                return Err(format!("must be at most {max}"));
+            }
+        }
+        Ok(value)
+    }
+    fn step(&self, text: &str, up: bool) -> Option<String> {
+        let value = match text.parse::<T>() {
+            Ok(value) => value.step_by(self.step, up),
+            Err(_) => self.min.unwrap_or(T::zero()),
+        };
+        let value = match (self.min, self.max) {
+            (Some(min), _) if value < min => min,
+            (_, Some(max)) if value > max => max,
+            _ => value,
+        };
+        Some(value.to_string())
+    }
+}
+
+/// A prompt for a number of type `T`, ex. `NumberInput::<u8>::new("Age:")`
+pub type NumberInput<T> = TypedInput<NumberParser<T>>;
+
+impl<T: Number + 'static> NumberInput<T> {
+    pub fn new(prompt: &str) -> Self {
+        TypedInput::with_parser(
+            prompt,
+            NumberParser {
+                min: None,
+                max: None,
+                step: T::one(),
+            },
+        )
+    }
+    /// Set the smallest allowed number
+    pub fn min(mut self, min: T) -> Self {
+        self.parser.min = Some(min);
+        self
+    }
+    /// Set the largest allowed number
+    pub fn max(mut self, max: T) -> Self {
+        self.parser.max = Some(max);
+        self
+    }
+    /// Set the amount the number changes by when Up or Down is pressed
+    pub fn step(mut self, step: T) -> Self {
+        self.parser.step = step;
+        self
+    }
+}
+
+/// A calendar date, written as `YYYY-MM-DD`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    fn is_leap_year(year: i32) -> bool {
+        (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+    }
+    /// Get the amount of days in a month of a year
+    pub fn days_in_month(year: i32, month: u8) -> u8 {
+        match month {
+            2 if Date::is_leap_year(year) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+    /// Create a date, if it exists
+    pub fn new(year: i32, month: u8, day: u8) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 || day > Date::days_in_month(year, month) {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+    /// Get the next day
+    pub fn next_day(self) -> Self {
+        if self.day < Date::days_in_month(self.year, self.month) {
+            Date {
+                day: self.day + 1,
+                ..self
+            }
+        } else if self.month < 12 {
+            Date {
+                month: self.month + 1,
+                day: 1,
+                ..self
+            }
+        } else {
+            Date {
+                year: self.year + 1,
+                month: 1,
+                day: 1,
+            }
+        }
+    }
+    /// Get the previous day
+    pub fn previous_day(self) -> Self {
+        if self.day > 1 {
+            Date {
+                day: self.day - 1,
+                ..self
+            }
+        } else if self.month > 1 {
+            let month = self.month - 1;
+            Date {
+                month,
+                day: Date::days_in_month(self.year, month),
+                ..self
+            }
+        } else {
+            Date {
+                year: self.year - 1,
+                month: 12,
+                day: 31,
+            }
+        }
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+    fn from_str(text: &str) -> std::result::Result<Self, String> {
+        let invalid = || format!("'{text}' is not a date (YYYY-MM-DD)");
+        let mut parts = text.splitn(3, '-');
+        let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let year = year.parse().map_err(|_| invalid())?;
+        let month = month.parse().map_err(|_| invalid())?;
+        let day = day.parse().map_err(|_| invalid())?;
+        Date::new(year, month, day).ok_or_else(|| format!("{text} doesn't exist"))
+    }
+}
+
+/// [Parser](InputParser) for [dates](Date). Up and Down move to the next and previous day.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DateParser {
+    pub min: Option<Date>,
+    pub max: Option<Date>,
+}
+
+impl InputParser for DateParser {
+    type Output = Date;
+    fn allows_char(&self, c: char) -> bool {
+        c.is_ascii_digit() || c == '-'
+    }
+    fn parse(&self, text: &str) -> std::result::Result<Date, String> {
+        let date: Date = text.parse()?;
+        if let Some(min) = self.min {
+            if date < min {
+                return Err(format!("must be {min} or later"));
+            }
+        }
+        if let Some(max) = self.max {
+            if date > max {
+                return Err(format!("must be {max} or earlier"));
+            }
+        }
+        Ok(date)
+    }
+    fn step(&self, text: &str, up: bool) -> Option<String> {
+        let date: Date = text.parse().ok()?;
+        let date = if up {
+            date.next_day()
+        } else {
+            date.previous_day()
+        };
+        Some(date.to_string())
+    }
+}
+
+/// A prompt for a [Date], written as `YYYY-MM-DD`
+pub type DateInput = TypedInput<DateParser>;
+
+impl DateInput {
+    pub fn new(prompt: &str) -> Self {
+        TypedInput::with_parser(prompt, DateParser::default())
+    }
+    /// Set the earliest allowed date
+    pub fn min(mut self, min: Date) -> Self {
+        self.parser.min = Some(min);
+        self
+    }
+    /// Set the latest allowed date
+    pub fn max(mut self, max: Date) -> Self {
+        self.parser.max = Some(max);
+        self
+    }
+}
+
+/// [Parser](InputParser) that accepts text fully matching a regular expression
+#[cfg(feature = "regex")]
+#[derive(Clone, Debug)]
+pub struct PatternParser {
+    /// Anchored to the start and end of the text by [new](PatternParser::new)
+    pub regex: regex::Regex,
+    /// Shown when the text doesn't match
+    pub message: String,
+}
+
+#[cfg(feature = "regex")]
+impl PatternParser {
+    /// Create a parser for text fully matching `pattern`. Fails if the pattern isn't a valid regular expression.
+    pub fn new(pattern: &str) -> std::result::Result<Self, regex::Error> {
+        // Anchoring the whole pattern makes alternations like `a|ab` match all of "ab"
+        let regex = regex::Regex::new(&format!("^(?:{pattern})$"))?;
+        Ok(PatternParser {
+            regex,
+            message: format!("must match {pattern}"),
+        })
+    }
+}
+
+#[cfg(feature = "regex")]
+impl InputParser for PatternParser {
+    type Output = String;
+    fn parse(&self, text: &str) -> std::result::Result<String, String> {
+        if self.regex.is_match(text) {
+            Ok(text.to_string())
+        } else {
+            Err(self.message.clone())
+        }
+    }
+}
+
+/// A prompt for text that must fully match a regular expression
+#[cfg(feature = "regex")]
+pub type PatternInput = TypedInput<PatternParser>;
+
+#[cfg(feature = "regex")]
+impl PatternInput {
+    /// Create a prompt for text matching `pattern`. Fails if the pattern isn't a valid regular expression.
+    pub fn new(prompt: &str, pattern: &str) -> std::result::Result<Self, regex::Error> {
+        Ok(TypedInput::with_parser(
+            prompt,
+            PatternParser::new(pattern)?,
+        ))
+    }
+    /// Set the message shown when the text doesn't match
+    pub fn message(mut self, message: &str) -> Self {
+        self.parser.message = message.to_string();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_date() {
+        assert_eq!("2024-02-29".parse(), Ok(Date::new(2024, 2, 29).unwrap()));
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("1900-02-29".parse::<Date>().is_err());
+        assert!("2000-02-29".parse::<Date>().is_ok());
+        assert!("2024-13-01".parse::<Date>().is_err());
+        assert!("2024-1".parse::<Date>().is_err());
+        assert!("today".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn date_display() {
+        assert_eq!(Date::new(7, 3, 9).unwrap().to_string(), "0007-03-09");
+    }
+
+    #[test]
+    fn step_days() {
+        let date = |text: &str| text.parse::<Date>().unwrap();
+        assert_eq!(date("2024-02-28").next_day(), date("2024-02-29"));
+        assert_eq!(date("2023-02-28").next_day(), date("2023-03-01"));
+        assert_eq!(date("2023-12-31").next_day(), date("2024-01-01"));
+        assert_eq!(date("2024-03-01").previous_day(), date("2024-02-29"));
+        assert_eq!(date("2024-01-01").previous_day(), date("2023-12-31"));
+    }
+
+    #[test]
+    fn date_parser_bounds() {
+        let parser = DateParser {
+            min: Date::new(2024, 1, 1),
+            max: Date::new(2024, 12, 31),
+        };
+        assert!(parser.parse("2024-06-15").is_ok());
+        assert!(parser.parse("2023-12-31").is_err());
+        assert!(parser.parse("2025-01-01").is_err());
+        assert_eq!(
+            parser.step("2024-06-15", true).as_deref(),
+            Some("2024-06-16")
+        );
+        assert_eq!(parser.step("not a date", true), None);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn pattern_matches_whole_text() {
+        let parser = PatternParser::new("a|ab").unwrap();
+        assert!(parser.parse("ab").is_ok());
+        assert!(parser.parse("a").is_ok());
+        assert!(parser.parse("abc").is_err());
+        assert!(parser.parse("xa").is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{Edit, TextInputData};
+use std::io::Result;
+
+/// How many steps an [UndoHistory] remembers
+const MAX_UNDO_STEPS: usize = 1000;
+
+/// Edits that can be undone with CTRL + Z and redone with CTRL + Y.
+///
+/// A step is the edits of one event, ex. a paste or replacing every search match. Characters typed or deleted one after another are merged into one step,
+/// with a new step for every word typed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UndoHistory {
+    undo: Vec<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+    /// Whether the next edit may be merged into the last step
+    #[cfg_attr(feature = "serde", serde(skip))]
+    mergeable: bool,
+}
+
+impl UndoHistory {
+    /// Record edits as a step to undo. This clears the steps to redo.
+    pub fn push(&mut self, edits: Vec<Edit>) {
+        let Some(first) = edits.first() else {
+            return;
+        };
+        self.redo.clear();
+        let merge = self.mergeable
+            && edits.len() == 1
+            && self
+                .undo
+                .last()
+                .and_then(|step| step.last())
+                .is_some_and(|last| continues(last, first));
+        match self.undo.last_mut() {
+            Some(step) if merge => step.extend(edits),
+            _ => self.undo.push(edits),
+        }
+        self.mergeable = true;
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    pub fn clear(&mut self) {
+        *self = UndoHistory::default();
+    }
+}
+
+// Whether an edit continues typing or deleting where the one before it left off
+fn continues(last: &Edit, next: &Edit) -> bool {
+    // A word typed after whitespace starts a new step
+    let new_word = last.inserted.ends_with(char::is_whitespace)
+        && !next.inserted.starts_with(char::is_whitespace);
+    let typing = last.removed.is_empty()
+        && next.removed.is_empty()
+        && next.start == last.new_end()
+        && !next.inserted.contains('\n')
+        && !new_word;
+    let deleting = last.inserted.is_empty()
+        && next.inserted.is_empty()
+        && (next.old_end() == last.start || next.start == last.start);
+    typing || deleting
+}
+
+impl TextInputData {
+    /// Undo the last step of the [undo history](TextInputData::undo_history), moving the cursor to where it was before it.
+    /// Returns whether there was a step to undo.
+    ///
+    /// Undoing is an edit like any other, so it is passed to [on_change](crate::CustomInputHandler::on_change), but it isn't added to the history itself.
+    /// If the step can't be undone, ex. because [text](TextInputData::text) was set directly or it is [protected](TextInputData::protected_ranges) now, the history is cleared.
+    pub fn undo(&mut self) -> Result<bool> {
+        if self.read_only {
+            self.reject_edit(self.cursor_x, self.cursor_y);
+            return Ok(false);
+        }
+        self.finish_edits();
+        let Some(step) = self.undo_history.undo.pop() else {
+            return Ok(false);
+        };
+        let mut applied = true;
+        for edit in step.iter().rev() {
+            applied = applied && self.apply_edit(edit.start, &edit.inserted, &edit.removed)?;
+        }
+        self.finish_history_edits(applied, step[0].cursor_before);
+        if applied {
+            self.undo_history.redo.push(step);
+        }
+        Ok(applied)
+    }
+    /// Redo the last step that was [undone](TextInputData::undo), moving the cursor to where it was after it. Returns whether there was a step to redo.
+    pub fn redo(&mut self) -> Result<bool> {
+        if self.read_only {
+            self.reject_edit(self.cursor_x, self.cursor_y);
+            return Ok(false);
+        }
+        self.finish_edits();
+        let Some(step) = self.undo_history.redo.pop() else {
+            return Ok(false);
+        };
+        let mut applied = true;
+        for edit in step.iter() {
+            applied = applied && self.apply_edit(edit.start, &edit.removed, &edit.inserted)?;
+        }
+        let cursor_after = step[step.len() - 1].cursor_after;
+        self.finish_history_edits(applied, cursor_after);
+        if applied {
+            self.undo_history.undo.push(step);
+        }
+        Ok(applied)
+    }
+    // Replace `old` at `start` with `new`, if the text there is still `old`
+    fn apply_edit(&mut self, start: (usize, usize), old: &str, new: &str) -> Result<bool> {
+        let end = crate::edit::end_position(start, old);
+        if self.get_text_between(start, end) != Some(old) {
+            return Ok(false);
+        }
+        self.replace_range(start, end, new)
+    }
+    // End the edits made by undoing or redoing, keeping them out of the history
+    fn finish_history_edits(&mut self, applied: bool, cursor: (usize, usize)) {
+        self.extra_cursors.clear();
+        self.block_selection = None;
+        if applied {
+            (self.cursor_x, self.cursor_y) = cursor;
+            self.clamp_positions();
+        } else {
+            self.undo_history.clear();
+        }
+        let edits = self.drain_pending_edits();
+        self.finished_edits.extend(edits);
+        self.undo_history.mergeable = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_data(text: &str) -> TextInputData {
+        let mut text_data = TextInputData::new(4);
+        text_data.load_text(text);
+        text_data.take_edits();
+        text_data
+    }
+
+    // Type like CoolInput does, taking the edits after every key press
+    fn type_str(text_data: &mut TextInputData, text: &str) {
+        for c in text.chars() {
+            text_data.write_char(c).unwrap();
+            text_data.take_edits();
+        }
+    }
+
+    #[test]
+    fn typing_is_undone_a_word_at_a_time() {
+        let mut text_data = text_data("");
+        type_str(&mut text_data, "hello big world");
+        assert!(text_data.undo().unwrap());
+        assert_eq!(text_data.text, "hello big ");
+        assert!(text_data.undo().unwrap());
+        assert_eq!(text_data.text, "hello ");
+        assert!(text_data.undo().unwrap());
+        assert_eq!(text_data.text, "");
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (0, 0));
+        assert!(!text_data.undo().unwrap());
+    }
+
+    #[test]
+    fn redo_and_new_edits() {
+        let mut text_data = text_data("abc");
+        text_data.cursor_x = 3;
+        text_data.write_str("def").unwrap();
+        text_data.take_edits();
+        text_data.undo().unwrap();
+        assert_eq!(text_data.text, "abc");
+        assert!(text_data.redo().unwrap());
+        assert_eq!(text_data.text, "abcdef");
+        assert_eq!((text_data.cursor_x, text_data.cursor_y), (6, 0));
+        assert!(!text_data.redo().unwrap());
+
+        text_data.undo().unwrap();
+        text_data.write_char('x').unwrap();
+        text_data.take_edits();
+        // A new edit clears what could be redone
+        assert!(!text_data.undo_history.can_redo());
+        text_data.undo().unwrap();
+        assert_eq!(text_data.text, "abc");
+    }
+
+    #[test]
+    fn pending_edits_are_undone() {
+        let mut text_data = text_data("a\nb");
+        text_data.selection_anchor = Some((0, 0));
+        (text_data.cursor_x, text_data.cursor_y) = (1, 1);
+        text_data.delete_selection().unwrap();
+        text_data.write_str("xy").unwrap();
+        assert_eq!(text_data.text, "xy");
+        assert!(text_data.undo().unwrap());
+        assert_eq!(text_data.text, "a\nb");
+    }
+
+    #[test]
+    fn backspace_is_undone_at_once() {
+        let mut text_data = text_data("hello");
+        text_data.cursor_x = 5;
+        for _ in 0..3 {
+            text_data
+                .remove_character(text_data.cursor_x, text_data.cursor_y)
+                .unwrap();
+            text_data.take_edits();
+        }
+        assert_eq!(text_data.text, "he");
+        text_data.undo().unwrap();
+        assert_eq!(text_data.text, "hello");
+    }
+
+    #[test]
+    fn undoing_is_an_edit_but_not_history() {
+        let mut text_data = text_data("");
+        type_str(&mut text_data, "ab");
+        text_data.undo().unwrap();
+        let edits = text_data.take_edits();
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            (edits[0].removed.as_str(), edits[0].inserted.as_str()),
+            ("ab", "")
+        );
+        assert!(!text_data.undo_history.can_undo());
+        assert!(text_data.undo_history.can_redo());
+    }
+
+    #[test]
+    fn history_is_cleared_when_it_no_longer_fits() {
+        let mut text_data = text_data("");
+        type_str(&mut text_data, "ab");
+        text_data.text = "changed".to_string();
+        assert!(!text_data.undo().unwrap());
+        assert_eq!(text_data.text, "changed");
+        assert!(!text_data.undo_history.can_redo());
+
+        type_str(&mut text_data, "x");
+        text_data.load_text("loaded");
+        assert!(!text_data.undo_history.can_undo());
+    }
+
+    #[test]
+    fn read_only_text_is_not_undone() {
+        let mut text_data = text_data("");
+        type_str(&mut text_data, "ab");
+        text_data.read_only = true;
+        assert!(!text_data.undo().unwrap());
+        assert_eq!(text_data.text, "ab");
+        text_data.read_only = false;
+        assert!(text_data.undo().unwrap());
+    }
+}
@@ -0,0 +1,416 @@
+use crate::search::{char_range, TextRange};
+use crate::{CoolInput, CustomInputHandler, Highlight};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Color, ContentStyle};
+use std::cmp;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Result, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Every this many lines, the byte offset of the line is kept in the line index
+const INDEX_STEP: usize = 1024;
+/// Size of the chunks the file is read in
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// A file that is read in pages as it is viewed, instead of loaded as a whole, for files too large to edit. Shown read-only by setting [CoolInput::viewer].
+///
+/// Lines are found as the file is read, and the byte offset of every 1024th line is kept, so going back to a line only reads from the closest one before it.
+/// Invalid UTF-8 is shown as `�`.
+#[derive(Debug)]
+pub struct PagedFile {
+    pub path: PathBuf,
+    file: File,
+    /// Byte offset of every `INDEX_STEP`th line
+    line_index: Vec<u64>,
+    /// Amount of lines whose start was found so far
+    known_lines: usize,
+    /// Whether the whole file was read, so every line is known
+    fully_scanned: bool,
+    /// The lines that were last read, starting at `page_start`
+    page: Vec<String>,
+    page_start: usize,
+}
+
+impl PagedFile {
+    /// Open a file without reading it yet
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(PagedFile {
+            path: path.as_ref().to_path_buf(),
+            file: File::open(path)?,
+            line_index: vec![0],
+            known_lines: 1,
+            fully_scanned: false,
+            page: Vec::new(),
+            page_start: 0,
+        })
+    }
+    /// Amount of lines found so far. Grows as more of the file is read, until it is [counted](PagedFile::count_lines).
+    pub fn amt_lines_known(&self) -> usize {
+        self.known_lines
+    }
+    /// Amount of lines, if the whole file was read
+    pub fn amt_lines(&self) -> Option<usize> {
+        self.fully_scanned.then_some(self.known_lines)
+    }
+    /// Read through the whole file to count its lines
+    pub fn count_lines(&mut self) -> io::Result<usize> {
+        if !self.fully_scanned {
+            self.read_from_line(self.known_lines - 1, |_, _| true)?;
+        }
+        Ok(self.known_lines)
+    }
+    /// Get up to `amount` lines, starting at the line `first`. Fewer lines are returned at the end of the file.
+    pub fn get_lines(&mut self, first: usize, amount: usize) -> io::Result<&[String]> {
+        let page_end = self.page_start + self.page.len();
+        let is_last_page = self.fully_scanned && page_end == self.known_lines;
+        let in_page = !self.page.is_empty()
+            && first >= self.page_start
+            && (first + amount <= page_end || is_last_page);
+        if !in_page {
+            let mut page = Vec::with_capacity(amount);
+            self.read_from_line(first, |_, line| {
+                page.push(String::from_utf8_lossy(line).to_string());
+                page.len() < amount
+            })?;
+            self.page = page;
+            self.page_start = first;
+        }
+        let start = cmp::min(first - self.page_start, self.page.len());
+        let end = cmp::min(start + amount, self.page.len());
+        Ok(&self.page[start..end])
+    }
+    /// Find the first match at or after the position `from`, or the last one before it, wrapping around at the ends of the file.
+    /// `find_in_line` gets the byte ranges of the matches in a line.
+    pub fn find(
+        &mut self,
+        from: (usize, usize),
+        forwards: bool,
+        find_in_line: impl Fn(&str) -> Vec<Range<usize>>,
+    ) -> io::Result<Option<TextRange>> {
+        // Positions are compared by line first
+        let from_key = (from.1, from.0);
+        if forwards {
+            let mut found = None;
+            self.read_from_line(from.1, |y, line| {
+                found = find_first(y, line, &find_in_line, |position| position >= from_key);
+                found.is_none()
+            })?;
+            if found.is_none() {
+                // Wrap around to the start
+                self.read_from_line(0, |y, line| {
+                    found = find_first(y, line, &find_in_line, |position| position < from_key);
+                    found.is_none() && y < from.1
+                })?;
+            }
+            return Ok(found);
+        }
+        // Read backwards one indexed block of lines at a time, keeping the last match of the block
+        let from_block = from.1 / INDEX_STEP;
+        self.count_lines_until(from.1)?;
+        for block in (0..=from_block).rev() {
+            if let Some(found) = self.find_last_in_block(block, &find_in_line, |p| p < from_key)? {
+                return Ok(Some(found));
+            }
+        }
+        // Wrap around to the end
+        let last_block = (self.count_lines()? - 1) / INDEX_STEP;
+        for block in (from_block..=last_block).rev() {
+            if let Some(found) = self.find_last_in_block(block, &find_in_line, |p| p >= from_key)? {
+                return Ok(Some(found));
+            }
+        }
+        Ok(None)
+    }
+    // Make sure the lines up to `line` are indexed
+    fn count_lines_until(&mut self, line: usize) -> io::Result<()> {
+        if line >= self.known_lines && !self.fully_scanned {
+            self.read_from_line(self.known_lines - 1, |y, _| y < line)?;
+        }
+        Ok(())
+    }
+    fn find_last_in_block(
+        &mut self,
+        block: usize,
+        find_in_line: &impl Fn(&str) -> Vec<Range<usize>>,
+        accept: impl Fn((usize, usize)) -> bool,
+    ) -> io::Result<Option<TextRange>> {
+        let mut found = None;
+        self.read_from_line(block * INDEX_STEP, |y, line| {
+            let line = String::from_utf8_lossy(line);
+            for range in find_in_line(&line) {
+                let range = char_range(y, &line, range);
+                if accept((range.0 .1, range.0 .0)) {
+                    found = Some(range);
+                }
+            }
+            y + 1 < (block + 1) * INDEX_STEP
+        })?;
+        Ok(found)
+    }
+    /// Call `f` with each line from `first`, until it returns false or the file ends. Lines are indexed as they are read.
+    fn read_from_line(
+        &mut self,
+        first: usize,
+        mut f: impl FnMut(usize, &[u8]) -> bool,
+    ) -> io::Result<()> {
+        // Start reading from the closest indexed line before the first one
+        let block = cmp::min(first, self.known_lines - 1) / INDEX_STEP;
+        let mut y = block * INDEX_STEP;
+        let mut offset = self.line_index[block];
+        self.file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::with_capacity(READ_BUFFER_SIZE, &self.file);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            offset += reader.read_until(b'\n', &mut line)? as u64;
+            let has_line_break = line.last() == Some(&b'\n');
+            if has_line_break {
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                if y + 1 == self.known_lines {
+                    self.known_lines += 1;
+                    if (y + 1) % INDEX_STEP == 0 {
+                        self.line_index.push(offset);
+                    }
+                }
+            } else {
+                self.fully_scanned = true;
+            }
+            if y >= first && !f(y, &line) {
+                break;
+            }
+            if !has_line_break {
+                break;
+            }
+            y += 1;
+        }
+        Ok(())
+    }
+}
+
+// Find the first match on a line whose start position (line, character) is accepted
+fn find_first(
+    y: usize,
+    line: &[u8],
+    find_in_line: &impl Fn(&str) -> Vec<Range<usize>>,
+    accept: impl Fn((usize, usize)) -> bool,
+) -> Option<TextRange> {
+    let line = String::from_utf8_lossy(line);
+    find_in_line(&line)
+        .into_iter()
+        .map(|range| char_range(y, &line, range))
+        .find(|range| accept((range.0 .1, range.0 .0)))
+}
+
+impl<H: CustomInputHandler> CoolInput<H> {
+    /// Get the amount of lines of the text, or the lines found so far of the [viewer](CoolInput::viewer)
+    pub fn get_amt_lines(&self) -> usize {
+        match &self.viewer {
+            Some(viewer) => viewer.amt_lines_known(),
+            None => self.text_data.get_amt_lines(),
+        }
+    }
+    // Scroll the viewer with a key press
+    pub(crate) fn handle_viewer_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
+        let input_transform = self.get_input_transform()?;
+        let (width, height) = (
+            input_transform.size.0 as usize,
+            input_transform.size.1 as usize,
+        );
+        match key_event.code {
+            KeyCode::Up => self.scroll_y = self.scroll_y.saturating_sub(1),
+            KeyCode::Down => self.scroll_y += 1,
+            KeyCode::PageUp => self.scroll_y = self.scroll_y.saturating_sub(height),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_y += height,
+            KeyCode::Left => self.scroll_x = self.scroll_x.saturating_sub(width / 2),
+            KeyCode::Right => self.scroll_x += width / 2,
+            KeyCode::Home => (self.scroll_x, self.scroll_y) = (0, 0),
+            KeyCode::End => {
+                if let Some(viewer) = &mut self.viewer {
+                    self.scroll_y = viewer.count_lines()?;
+                }
+            }
+            KeyCode::Char('/') if !key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.open_search()
+            }
+            _ => return Ok(()),
+        }
+        self.clamp_viewer_scroll()
+    }
+    // Handle a key press while the search prompt of the viewer is open. Matches are only searched for on Enter, as that may read the whole file.
+    pub(crate) fn handle_viewer_search_key_press(&mut self, key_event: KeyEvent) -> Result<()> {
+        let Some(search) = &mut self.search else {
+            return Ok(());
+        };
+        let typing = !key_event
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key_event.code {
+            KeyCode::Esc => {
+                self.search = None;
+                return Ok(());
+            }
+            #[cfg(feature = "regex")]
+            KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::ALT) => {
+                search.regex = !search.regex;
+            }
+            KeyCode::Char(c) if typing => search.query.push(c),
+            KeyCode::Backspace => {
+                search.query.pop();
+            }
+            KeyCode::Enter | KeyCode::Down => return self.go_to_viewer_match(true),
+            KeyCode::Up => return self.go_to_viewer_match(false),
+            _ => return Ok(()),
+        }
+        search.found = None;
+        search.not_found = false;
+        Ok(())
+    }
+    // Search the viewer for the next or previous match, starting at the top of the screen
+    fn go_to_viewer_match(&mut self, forwards: bool) -> Result<()> {
+        let (Some(search), Some(viewer)) = (&mut self.search, &mut self.viewer) else {
+            return Ok(());
+        };
+        let Ok(find_in_line) = search.line_matcher() else {
+            return Ok(());
+        };
+        let from = match search.found {
+            Some(((x, y), _)) if forwards => (x + 1, y),
+            Some((start, _)) => start,
+            None => (0, self.scroll_y),
+        };
+        let found = viewer.find(from, forwards, find_in_line)?;
+        search.found = found;
+        search.not_found = found.is_none();
+        match found {
+            Some((start, _)) => self.scroll_viewer_to(start),
+            None => Ok(()),
+        }
+    }
+    // Keep the last page of the viewer filled, without reading further than the screen
+    pub(crate) fn clamp_viewer_scroll(&mut self) -> Result<()> {
+        let height = self.get_input_transform()?.size.1 as usize;
+        let Some(viewer) = &mut self.viewer else {
+            return Ok(());
+        };
+        let amt_lines = viewer.get_lines(self.scroll_y, height)?.len();
+        if amt_lines < height {
+            let last_line = self.scroll_y + amt_lines;
+            self.scroll_y = last_line.saturating_sub(height);
+        }
+        Ok(())
+    }
+    // Scroll the viewer so a line is in view, with some lines above it
+    pub(crate) fn scroll_viewer_to(&mut self, (x, y): (usize, usize)) -> Result<()> {
+        let input_transform = self.get_input_transform()?;
+        let (width, height) = (
+            input_transform.size.0 as usize,
+            input_transform.size.1 as usize,
+        );
+        if y < self.scroll_y || y >= self.scroll_y + height {
+            self.scroll_y = y.saturating_sub(height / 3);
+        }
+        if x < self.scroll_x || x >= self.scroll_x + width {
+            self.scroll_x = x.saturating_sub(width / 2);
+        }
+        self.clamp_viewer_scroll()
+    }
+    // Highlight the search matches on the lines of the viewer that are on screen
+    pub(crate) fn get_viewer_search_highlights(&mut self) -> Vec<Highlight> {
+        let height = self.get_input_transform().map_or(0, |t| t.size.1 as usize);
+        let (Some(search), Some(viewer)) = (&self.search, &mut self.viewer) else {
+            return Vec::new();
+        };
+        let Ok(find_in_line) = search.line_matcher() else {
+            return Vec::new();
+        };
+        let Ok(lines) = viewer.get_lines(self.scroll_y, height) else {
+            return Vec::new();
+        };
+        let style = ContentStyle {
+            foreground_color: Some(Color::Black),
+            background_color: Some(Color::Yellow),
+            ..ContentStyle::new()
+        };
+        let mut highlights = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            for range in find_in_line(line) {
+                let ((start, y), (end, _)) = char_range(self.scroll_y + i, line, range);
+                let mut style = style;
+                if search.found == Some(((start, y), (end, y))) {
+                    style.background_color = Some(Color::DarkYellow);
+                }
+                highlights.push(Highlight::new(y, start, end, style));
+            }
+        }
+        highlights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Write a file with a line for every number, and open it
+    fn numbered_file(name: &str, amt_lines: usize) -> PagedFile {
+        let path =
+            std::env::temp_dir().join(format!("cool-rust-input-{}-{name}", std::process::id()));
+        let text: String = (0..amt_lines).map(|i| format!("line {i}\r\n")).collect();
+        fs::write(&path, text).unwrap();
+        PagedFile::open(&path).unwrap()
+    }
+
+    fn find_text(query: &'static str) -> impl Fn(&str) -> Vec<Range<usize>> {
+        move |line| {
+            line.match_indices(query)
+                .map(|(start, found)| start..start + found.len())
+                .collect()
+        }
+    }
+
+    #[test]
+    fn get_lines() {
+        let mut file = numbered_file("get_lines", 3000);
+        assert_eq!(file.get_lines(2500, 2).unwrap(), ["line 2500", "line 2501"]);
+        assert_eq!(file.get_lines(0, 1).unwrap(), ["line 0"]);
+        // The last line break is followed by an empty line
+        assert_eq!(file.count_lines().unwrap(), 3001);
+        fs::remove_file(&file.path).unwrap();
+    }
+
+    #[test]
+    fn find_forwards() {
+        let mut file = numbered_file("find_forwards", 3000);
+        let found = file.find((0, 0), true, find_text("line 2048")).unwrap();
+        assert_eq!(found, Some(((0, 2048), (9, 2048))));
+        // Matches before `from` are skipped
+        let found = file.find((3, 5), true, find_text("1")).unwrap();
+        assert_eq!(found, Some(((5, 10), (6, 10))));
+        fs::remove_file(&file.path).unwrap();
+    }
+
+    #[test]
+    fn find_backwards() {
+        let mut file = numbered_file("find_backwards", 3000);
+        let found = file.find((0, 2100), false, find_text("line 1")).unwrap();
+        assert_eq!(found, Some(((0, 1999), (6, 1999))));
+        fs::remove_file(&file.path).unwrap();
+    }
+
+    #[test]
+    fn find_wraps_around() {
+        let mut file = numbered_file("find_wraps_around", 3000);
+        let found = file.find((0, 2500), true, find_text("line 27")).unwrap();
+        assert_eq!(found, Some(((0, 2700), (7, 2700))));
+        let found = file.find((0, 2800), true, find_text("line 27")).unwrap();
+        assert_eq!(found, Some(((0, 27), (7, 27))));
+        let found = file.find((0, 5), false, find_text("line 29")).unwrap();
+        assert_eq!(found, Some(((0, 2999), (7, 2999))));
+        assert_eq!(file.find((0, 0), true, find_text("nope")).unwrap(), None);
+        fs::remove_file(&file.path).unwrap();
+    }
+}